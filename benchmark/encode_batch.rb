@@ -0,0 +1,25 @@
+require "bundler/setup"
+require "benchmark"
+require "tokenizers"
+
+tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+texts = ["I can feel the magic, can you?"] * 10_000
+
+# encode_batch releases the GVL while encoding, so other threads keep
+# running and the inputs are encoded in parallel
+Benchmark.bm(20) do |x|
+  x.report("encode") do
+    texts.each { |text| tokenizer.encode(text) }
+  end
+
+  x.report("encode_batch") do
+    tokenizer.encode_batch(texts)
+  end
+
+  x.report("encode_batch (busy)") do
+    counter = 0
+    thread = Thread.new { loop { counter += 1 } }
+    tokenizer.encode_batch(texts)
+    thread.kill
+  end
+end
@@ -2,8 +2,8 @@ use std::sync::{Arc, RwLock};
 
 use magnus::typed_data::DataTypeBuilder;
 use magnus::{
-    function, memoize, method, Class, DataType, DataTypeFunctions, Module, Object, RClass, RModule,
-    TypedData,
+    function, memoize, method, Class, DataType, DataTypeFunctions, Module, Object, RArray, RClass,
+    RModule, TypedData,
 };
 use serde::{Deserialize, Serialize};
 use tk::decoders::bpe::BPEDecoder;
@@ -12,6 +12,7 @@ use tk::decoders::byte_level::ByteLevel;
 use tk::decoders::ctc::CTC;
 use tk::decoders::fuse::Fuse;
 use tk::decoders::metaspace::Metaspace;
+use tk::decoders::sequence::Sequence;
 use tk::decoders::strip::Strip;
 use tk::decoders::wordpiece::WordPiece;
 use tk::decoders::DecoderWrapper;
@@ -62,6 +63,10 @@ macro_rules! setter {
     }};
 }
 impl RbDecoder {
+    pub fn decode(&self, tokens: Vec<String>) -> RbResult<String> {
+        self.decoder.decode(tokens).map_err(RbError::from)
+    }
+
     pub fn bpe_suffix(&self) -> String {
         getter!(self, BPE, suffix.clone())
     }
@@ -207,6 +212,20 @@ impl RbReplaceDecoder {
     }
 }
 
+pub struct RbSequenceDecoder {}
+
+impl RbSequenceDecoder {
+    pub fn new(decoders: RArray) -> RbResult<RbDecoder> {
+        let mut sequence = Vec::with_capacity(decoders.len());
+        for d in decoders.each() {
+            let decoder: &RbDecoder = d?.try_convert()?;
+            let RbDecoderWrapper::Wrapped(ref inner) = decoder.decoder;
+            sequence.push(inner.read().unwrap().clone());
+        }
+        Ok(Sequence::new(sequence).into())
+    }
+}
+
 pub struct RbStripDecoder {}
 
 impl RbStripDecoder {
@@ -310,6 +329,11 @@ unsafe impl TypedData for RbDecoder {
                     class.undef_alloc_func();
                     class
                 }),
+                DecoderWrapper::Sequence(_) => *memoize!(RClass: {
+                    let class: RClass = crate::decoders().const_get("Sequence").unwrap();
+                    class.undef_alloc_func();
+                    class
+                }),
                 DecoderWrapper::Strip(_) => *memoize!(RClass: {
                     let class: RClass = crate::decoders().const_get("Strip").unwrap();
                     class.undef_alloc_func();
@@ -320,7 +344,6 @@ unsafe impl TypedData for RbDecoder {
                     class.undef_alloc_func();
                     class
                 }),
-                _ => todo!(),
             },
         }
     }
@@ -328,6 +351,7 @@ unsafe impl TypedData for RbDecoder {
 
 pub fn decoders(module: &RModule) -> RbResult<()> {
     let decoder = module.define_class("Decoder", Default::default())?;
+    decoder.define_method("decode", method!(RbDecoder::decode, 1))?;
 
     let class = module.define_class("BPEDecoder", decoder)?;
     class.define_singleton_method("_new", function!(RbBPEDecoder::new, 1))?;
@@ -362,6 +386,9 @@ pub fn decoders(module: &RModule) -> RbResult<()> {
     let class = module.define_class("Replace", decoder)?;
     class.define_singleton_method("new", function!(RbReplaceDecoder::new, 2))?;
 
+    let class = module.define_class("Sequence", decoder)?;
+    class.define_singleton_method("new", function!(RbSequenceDecoder::new, 1))?;
+
     let class = module.define_class("Strip", decoder)?;
     class.define_singleton_method("_new", function!(RbStripDecoder::new, 3))?;
     class.define_method("content", method!(RbDecoder::strip_content, 0))?;
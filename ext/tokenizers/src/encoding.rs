@@ -1,57 +1,88 @@
-use magnus::RArray;
-use tk::{Encoding, Offsets};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use magnus::{exception, Error, RArray};
+use tk::{Encoding, Offsets, PaddingDirection, TruncationDirection};
+
+use super::RbResult;
 
 #[magnus::wrap(class = "Tokenizers::Encoding")]
 #[repr(transparent)]
 pub struct RbEncoding {
-    pub encoding: Encoding,
+    pub encoding: RefCell<Encoding>,
 }
 
 impl From<Encoding> for RbEncoding {
     fn from(v: Encoding) -> Self {
-        Self { encoding: v }
+        Self {
+            encoding: RefCell::new(v),
+        }
     }
 }
 
 impl RbEncoding {
+    pub fn merge(encodings: RArray, growing_offsets: bool) -> RbResult<Self> {
+        let mut merged = Vec::with_capacity(encodings.len());
+        for e in encodings.each() {
+            let encoding: &RbEncoding = e?.try_convert()?;
+            merged.push(encoding.encoding.borrow().clone());
+        }
+        Ok(Encoding::merge(merged, growing_offsets).into())
+    }
+
     pub fn n_sequences(&self) -> usize {
-        self.encoding.n_sequences()
+        self.encoding.borrow().n_sequences()
     }
 
     pub fn ids(&self) -> Vec<u32> {
-        self.encoding.get_ids().to_vec()
+        self.encoding.borrow().get_ids().to_vec()
     }
 
     pub fn tokens(&self) -> Vec<String> {
-        self.encoding.get_tokens().to_vec()
+        self.encoding.borrow().get_tokens().to_vec()
     }
 
     pub fn word_ids(&self) -> Vec<Option<u32>> {
-        self.encoding.get_word_ids().to_vec()
+        self.encoding.borrow().get_word_ids().to_vec()
     }
 
     pub fn sequence_ids(&self) -> Vec<Option<usize>> {
-        self.encoding.get_sequence_ids()
+        self.encoding.borrow().get_sequence_ids()
+    }
+
+    pub fn is_continuation(&self) -> Vec<bool> {
+        let encoding = self.encoding.borrow();
+        let word_ids = encoding.get_word_ids();
+        let sequence_ids = encoding.get_sequence_ids();
+        (0..word_ids.len())
+            .map(|i| {
+                i > 0
+                    && word_ids[i].is_some()
+                    && word_ids[i] == word_ids[i - 1]
+                    && sequence_ids[i] == sequence_ids[i - 1]
+            })
+            .collect()
     }
 
     pub fn type_ids(&self) -> Vec<u32> {
-        self.encoding.get_type_ids().to_vec()
+        self.encoding.borrow().get_type_ids().to_vec()
     }
 
     pub fn offsets(&self) -> Vec<(usize, usize)> {
-        self.encoding.get_offsets().to_vec()
+        self.encoding.borrow().get_offsets().to_vec()
     }
 
     pub fn special_tokens_mask(&self) -> Vec<u32> {
-        self.encoding.get_special_tokens_mask().to_vec()
+        self.encoding.borrow().get_special_tokens_mask().to_vec()
     }
 
     pub fn attention_mask(&self) -> Vec<u32> {
-        self.encoding.get_attention_mask().to_vec()
+        self.encoding.borrow().get_attention_mask().to_vec()
     }
 
     pub fn overflowing(&self) -> RArray {
         self.encoding
+            .borrow()
             .get_overflowing()
             .clone()
             .into_iter()
@@ -60,32 +91,84 @@ impl RbEncoding {
     }
 
     pub fn word_to_tokens(&self, word_index: u32, sequence_index: usize) -> Option<(usize, usize)> {
-        self.encoding.word_to_tokens(word_index, sequence_index)
+        self.encoding.borrow().word_to_tokens(word_index, sequence_index)
     }
 
     pub fn word_to_chars(&self, word_index: u32, sequence_index: usize) -> Option<Offsets> {
-        self.encoding.word_to_chars(word_index, sequence_index)
+        self.encoding.borrow().word_to_chars(word_index, sequence_index)
     }
 
     pub fn token_to_sequence(&self, token_index: usize) -> Option<usize> {
-        self.encoding.token_to_sequence(token_index)
+        self.encoding.borrow().token_to_sequence(token_index)
     }
 
     pub fn token_to_chars(&self, token_index: usize) -> Option<Offsets> {
-        let (_, offsets) = self.encoding.token_to_chars(token_index)?;
+        let (_, offsets) = self.encoding.borrow().token_to_chars(token_index)?;
         Some(offsets)
     }
 
     pub fn token_to_word(&self, token_index: usize) -> Option<u32> {
-        let (_, word_idx) = self.encoding.token_to_word(token_index)?;
+        let (_, word_idx) = self.encoding.borrow().token_to_word(token_index)?;
         Some(word_idx)
     }
 
     pub fn char_to_token(&self, char_pos: usize, sequence_index: usize) -> Option<usize> {
-        self.encoding.char_to_token(char_pos, sequence_index)
+        self.encoding.borrow().char_to_token(char_pos, sequence_index)
     }
 
     pub fn char_to_word(&self, char_pos: usize, sequence_index: usize) -> Option<u32> {
-        self.encoding.char_to_word(char_pos, sequence_index)
+        self.encoding.borrow().char_to_word(char_pos, sequence_index)
+    }
+
+    pub fn truncate(&self, max_length: usize, stride: usize, direction: String) -> RbResult<()> {
+        let direction = match direction.as_str() {
+            "left" => TruncationDirection::Left,
+            "right" => TruncationDirection::Right,
+            _ => return Err(Error::new(exception::arg_error(), "The direction value must be 'left' or 'right'")),
+        };
+        if max_length > 0 && stride >= max_length {
+            return Err(Error::new(exception::arg_error(), "stride must be less than max_length"));
+        }
+        self.encoding.borrow_mut().truncate(max_length, stride, direction);
+        Ok(())
+    }
+
+    // tokens start...stop of a single sequence as a new encoding, with offsets and
+    // word ids shifted so they're relative to a larger document
+    pub fn slice(&self, start: usize, stop: usize, char_offset: usize, word_offset: u32) -> RbResult<Self> {
+        let encoding = self.encoding.borrow();
+        if start > stop || stop > encoding.len() {
+            return Err(Error::new(exception::index_error(), "token range out of bounds"));
+        }
+        Ok(Encoding::new(
+            encoding.get_ids()[start..stop].to_vec(),
+            encoding.get_type_ids()[start..stop].to_vec(),
+            encoding.get_tokens()[start..stop].to_vec(),
+            encoding.get_word_ids()[start..stop]
+                .iter()
+                .map(|w| w.map(|w| w + word_offset))
+                .collect(),
+            encoding.get_offsets()[start..stop]
+                .iter()
+                .map(|(s, e)| (s + char_offset, e + char_offset))
+                .collect(),
+            encoding.get_special_tokens_mask()[start..stop].to_vec(),
+            encoding.get_attention_mask()[start..stop].to_vec(),
+            vec![],
+            HashMap::new(),
+        )
+        .into())
+    }
+
+    pub fn pad(&self, length: usize, direction: String, pad_id: u32, pad_type_id: u32, pad_token: String) -> RbResult<()> {
+        let direction = match direction.as_str() {
+            "left" => PaddingDirection::Left,
+            "right" => PaddingDirection::Right,
+            _ => return Err(Error::new(exception::arg_error(), "The direction value must be 'left' or 'right'")),
+        };
+        self.encoding
+            .borrow_mut()
+            .pad(length, pad_id, pad_type_id, &pad_token, direction);
+        Ok(())
     }
 }
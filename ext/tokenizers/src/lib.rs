@@ -15,10 +15,13 @@ mod utils;
 
 use encoding::RbEncoding;
 use error::RbError;
-use tokenizer::RbTokenizer;
+use tokenizer::{RbAddedToken, RbTokenizer};
 use utils::RbRegex;
 
 use magnus::{define_module, function, memoize, method, prelude::*, Error, RModule};
+use tk::utils::parallelism::{
+    get_parallelism, has_parallelism_been_used, is_parallelism_configured, set_parallelism, ENV_VARIABLE,
+};
 
 type RbResult<T> = Result<T, Error>;
 
@@ -50,30 +53,75 @@ fn trainers() -> RModule {
     *memoize!(RModule: module().const_get("Trainers").unwrap())
 }
 
+// forking after the thread pool has been used can deadlock the child process
+// (common with Puma and Sidekiq), so disable parallelism in the child and warn
+extern "C" fn child_after_fork() {
+    if has_parallelism_been_used() && !is_parallelism_configured() {
+        eprintln!(
+            "tokenizers: The current process just got forked, after parallelism has \
+            already been used. Disabling parallelism to avoid deadlocks..."
+        );
+        eprintln!("To disable this warning, you can either:");
+        eprintln!(
+            "\t- Avoid using `tokenizers` before the fork if possible\n\
+            \t- Explicitly set the environment variable {}=(true | false)",
+            ENV_VARIABLE
+        );
+        set_parallelism(false);
+    }
+}
+
+// keep in sync with the tokenizers version in Cargo.toml
+const TK_VERSION: &str = "0.13.3";
+
 #[magnus::init]
 fn init() -> RbResult<()> {
+    #[cfg(unix)]
+    unsafe {
+        libc::pthread_atfork(None, None, Some(child_after_fork));
+    }
+
     let module = module();
+    module.const_set("TK_VERSION", TK_VERSION)?;
+    module.define_singleton_method("parallelism", function!(get_parallelism, 0))?;
+    module.define_singleton_method("parallelism=", function!(set_parallelism, 1))?;
 
     let class = module.define_class("Tokenizer", Default::default())?;
     class.define_singleton_method("new", function!(RbTokenizer::from_model, 1))?;
+    class.define_singleton_method("from_str", function!(RbTokenizer::from_str, 1))?;
     class.define_singleton_method("from_file", function!(RbTokenizer::from_file, 1))?;
+    tokenizer::define_allocator(class);
+    class.define_method("initialize_copy", method!(RbTokenizer::initialize_copy, 1))?;
     class.define_method(
         "add_special_tokens",
         method!(RbTokenizer::add_special_tokens, 1),
     )?;
-    class.define_method("train", method!(RbTokenizer::train, 2))?;
+    class.define_method("_train", method!(RbTokenizer::train, 3))?;
+    class.define_method("_train_from_iterator", method!(RbTokenizer::train_from_iterator, 3))?;
     class.define_method("_save", method!(RbTokenizer::save, 2))?;
     class.define_method("add_tokens", method!(RbTokenizer::add_tokens, 1))?;
+    class.define_method("added_tokens_decoder", method!(RbTokenizer::added_tokens_decoder, 0))?;
+    class.define_method("export_added_tokens", method!(RbTokenizer::export_added_tokens, 1))?;
+    class.define_method("import_added_tokens", method!(RbTokenizer::import_added_tokens, 1))?;
     class.define_method("_encode", method!(RbTokenizer::encode, 4))?;
+    class.define_method("normalize", method!(RbTokenizer::normalize, 1))?;
+    class.define_method("_encode_profile", method!(RbTokenizer::encode_profile, 3))?;
     class.define_method("_encode_batch", method!(RbTokenizer::encode_batch, 3))?;
-    class.define_method("_decode", method!(RbTokenizer::decode, 2))?;
-    class.define_method("_decode_batch", method!(RbTokenizer::decode_batch, 2))?;
+    class.define_method("_decode", method!(RbTokenizer::decode, 3))?;
+    class.define_method("_decode_batch", method!(RbTokenizer::decode_batch, 3))?;
+    class.define_method("_decode_batch_with_offsets", method!(RbTokenizer::decode_batch_with_offsets, 2))?;
+    class.define_method("model", method!(RbTokenizer::model, 0))?;
+    class.define_method("model=", method!(RbTokenizer::set_model, 1))?;
+    class.define_method("decoder", method!(RbTokenizer::decoder, 0))?;
     class.define_method("decoder=", method!(RbTokenizer::set_decoder, 1))?;
+    class.define_method("pre_tokenizer", method!(RbTokenizer::pre_tokenizer, 0))?;
     class.define_method("pre_tokenizer=", method!(RbTokenizer::set_pre_tokenizer, 1))?;
+    class.define_method("post_processor", method!(RbTokenizer::post_processor, 0))?;
     class.define_method(
         "post_processor=",
         method!(RbTokenizer::set_post_processor, 1),
     )?;
+    class.define_method("normalizer", method!(RbTokenizer::normalizer, 0))?;
     class.define_method("normalizer=", method!(RbTokenizer::set_normalizer, 1))?;
     class.define_method("token_to_id", method!(RbTokenizer::token_to_id, 1))?;
     class.define_method("id_to_token", method!(RbTokenizer::id_to_token, 1))?;
@@ -88,12 +136,23 @@ fn init() -> RbResult<()> {
     class.define_method("_vocab_size", method!(RbTokenizer::vocab_size, 1))?;
     class.define_method("_to_s", method!(RbTokenizer::to_str, 1))?;
 
+    let class = module.define_class("AddedToken", Default::default())?;
+    class.define_singleton_method("_new", function!(RbAddedToken::new, 6))?;
+    class.define_method("content", method!(RbAddedToken::content, 0))?;
+    class.define_method("special", method!(RbAddedToken::special, 0))?;
+    class.define_method("single_word", method!(RbAddedToken::single_word, 0))?;
+    class.define_method("lstrip", method!(RbAddedToken::lstrip, 0))?;
+    class.define_method("rstrip", method!(RbAddedToken::rstrip, 0))?;
+    class.define_method("normalized", method!(RbAddedToken::normalized, 0))?;
+
     let class = module.define_class("Encoding", Default::default())?;
+    class.define_singleton_method("_merge", function!(RbEncoding::merge, 2))?;
     class.define_method("n_sequences", method!(RbEncoding::n_sequences, 0))?;
     class.define_method("ids", method!(RbEncoding::ids, 0))?;
     class.define_method("tokens", method!(RbEncoding::tokens, 0))?;
     class.define_method("word_ids", method!(RbEncoding::word_ids, 0))?;
     class.define_method("sequence_ids", method!(RbEncoding::sequence_ids, 0))?;
+    class.define_method("is_continuation", method!(RbEncoding::is_continuation, 0))?;
     class.define_method("type_ids", method!(RbEncoding::type_ids, 0))?;
     class.define_method("offsets", method!(RbEncoding::offsets, 0))?;
     class.define_method(
@@ -112,9 +171,13 @@ fn init() -> RbResult<()> {
     class.define_method("token_to_word", method!(RbEncoding::token_to_word, 1))?;
     class.define_method("_char_to_token", method!(RbEncoding::char_to_token, 2))?;
     class.define_method("_char_to_word", method!(RbEncoding::char_to_word, 2))?;
+    class.define_method("_truncate", method!(RbEncoding::truncate, 3))?;
+    class.define_method("_slice", method!(RbEncoding::slice, 4))?;
+    class.define_method("_pad", method!(RbEncoding::pad, 5))?;
 
     let class = module.define_class("Regex", Default::default())?;
     class.define_singleton_method("new", function!(RbRegex::new, 1))?;
+    class.define_method("pattern", method!(RbRegex::pattern, 0))?;
 
     let models = module.define_module("Models")?;
     let pre_tokenizers = module.define_module("PreTokenizers")?;
@@ -67,6 +67,17 @@ where
     }
 }
 
+// upstream rejects 0.0 when building or loading a BPE, and it's the same as no dropout
+fn validate_dropout(dropout: Option<f32>) -> RbResult<Option<f32>> {
+    match dropout {
+        Some(dropout) if !(0.0..=1.0).contains(&dropout) => {
+            Err(Error::new(exception::arg_error(), "dropout must be between 0 and 1"))
+        }
+        Some(dropout) if dropout == 0.0 => Ok(None),
+        dropout => Ok(dropout),
+    }
+}
+
 pub struct RbBPE {}
 
 impl RbBPE {
@@ -77,8 +88,9 @@ impl RbBPE {
         }
 
         let value: Value = kwargs.delete(Symbol::new("dropout"))?;
-        if !value.is_nil() {
-            builder = builder.dropout(value.try_convert()?);
+        let dropout: Option<f32> = if value.is_nil() { None } else { Some(value.try_convert()?) };
+        if let Some(dropout) = validate_dropout(dropout)? {
+            builder = builder.dropout(dropout);
         }
 
         let value: Value = kwargs.delete(Symbol::new("unk_token"))?;
@@ -150,12 +162,27 @@ macro_rules! setter {
 }
 
 impl RbModel {
+    pub fn tokenize_str(&self, sequence: String) -> RbResult<Vec<(String, u32)>> {
+        self.model
+            .read()
+            .unwrap()
+            .tokenize(&sequence)
+            .map(|tokens| tokens.into_iter().map(|t| (t.value, t.id)).collect())
+            .map_err(RbError::from)
+    }
+
+    pub fn vocab_size(&self) -> usize {
+        self.model.read().unwrap().get_vocab_size()
+    }
+
     pub fn bpe_dropout(&self) -> Option<f32> {
         getter!(self, BPE, dropout)
     }
 
-    pub fn bpe_set_dropout(&self, dropout: Option<f32>) {
+    pub fn bpe_set_dropout(&self, dropout: Option<f32>) -> RbResult<()> {
+        let dropout = validate_dropout(dropout)?;
         setter!(self, BPE, dropout, dropout);
+        Ok(())
     }
 
     pub fn bpe_unk_token(&self) -> Option<String> {
@@ -188,6 +215,7 @@ impl RbModel {
 
     pub fn bpe_set_continuing_subword_prefix(&self, continuing_subword_prefix: Option<String>) {
         setter!(self, BPE, continuing_subword_prefix, continuing_subword_prefix);
+        self.bpe_clear_cache();
     }
 
     pub fn bpe_end_of_word_suffix(&self) -> Option<String> {
@@ -196,6 +224,14 @@ impl RbModel {
 
     pub fn bpe_set_end_of_word_suffix(&self, end_of_word_suffix: Option<String>) {
         setter!(self, BPE, end_of_word_suffix, end_of_word_suffix);
+        self.bpe_clear_cache();
+    }
+
+    // cached words are built with the previous prefix and suffix
+    fn bpe_clear_cache(&self) {
+        if let ModelWrapper::BPE(ref mo) = *self.model.read().unwrap() {
+            mo.clear_cache();
+        }
     }
 
     pub fn word_level_unk_token(&self) -> String {
@@ -354,6 +390,8 @@ unsafe impl TypedData for RbModel {
 
 pub fn models(module: &RModule) -> RbResult<()> {
     let model = module.define_class("Model", Default::default())?;
+    model.define_method("tokenize", method!(RbModel::tokenize_str, 1))?;
+    model.define_method("vocab_size", method!(RbModel::vocab_size, 0))?;
 
     let class = module.define_class("BPE", model)?;
     class.define_singleton_method("_new", function!(RbBPE::new, 3))?;
@@ -1,9 +1,10 @@
 use std::sync::{Arc, RwLock};
 
+use deunicode::deunicode_char;
 use magnus::typed_data::DataTypeBuilder;
 use magnus::{
-    function, memoize, method, Class, DataType, DataTypeFunctions, Module, Object, RArray, RClass, RModule,
-    TypedData,
+    exception, function, memoize, method, Class, DataType, DataTypeFunctions, Error, Module, Object, RArray,
+    RClass, RModule, TypedData, Value,
 };
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
@@ -11,6 +12,8 @@ use tk::normalizers::{
     BertNormalizer, Lowercase, Nmt, NormalizerWrapper, Replace, Prepend, Strip, StripAccents,
     NFC, NFD, NFKC, NFKD,
 };
+use tk::normalizer::Range;
+use tk::tokenizer::Offsets;
 use tk::{NormalizedString, Normalizer};
 
 use super::utils::*;
@@ -27,11 +30,108 @@ impl RbNormalizer {
         RbNormalizer { normalizer }
     }
 
-    pub fn normalize_str(&self, sequence: String) -> RbResult<String> {
-        let mut normalized = NormalizedString::from(sequence);
+    // borrowing the Ruby string's bytes wouldn't save an allocation, as NormalizedString
+    // only takes an owned String (From<&str> calls to_owned), so the copy is made here
+    pub fn normalize_str(&self, sequence: Value) -> RbResult<String> {
+        let mut normalized = NormalizedString::from(utf8_string(sequence)?);
         self.normalizer.normalize(&mut normalized).map_err(RbError::from)?;
         Ok(normalized.get().to_owned())
     }
+
+    pub fn from_str(json: String) -> RbResult<Self> {
+        serde_json::from_str(&json).map_err(|e| RbError::from(e.into()))
+    }
+
+    pub fn to_str(&self, pretty: bool) -> RbResult<String> {
+        if pretty {
+            serde_json::to_string_pretty(self)
+        } else {
+            serde_json::to_string(self)
+        }
+        .map_err(|e| RbError::from(e.into()))
+    }
+
+    pub fn eq(&self, other: Value) -> RbResult<bool> {
+        match other.try_convert::<&RbNormalizer>() {
+            Ok(other) => serialized_eq(self, other),
+            Err(_) => Ok(false),
+        }
+    }
+
+    pub fn hash(&self) -> RbResult<i64> {
+        serialized_hash(self)
+    }
+
+    pub fn inspect(rb_self: Value) -> RbResult<String> {
+        let normalizer: &RbNormalizer = rb_self.try_convert()?;
+        serialized_inspect(rb_self, normalizer)
+    }
+
+    pub fn normalize_with_changes(&self, sequence: Value) -> RbResult<(String, Vec<(Offsets, Offsets)>)> {
+        let mut normalized = NormalizedString::from(utf8_string(sequence)?);
+        self.normalizer.normalize(&mut normalized).map_err(RbError::from)?;
+
+        let original = normalized.get_original();
+        let text = normalized.get();
+
+        // group the normalized chars by the part of the original they come from
+        let mut spans: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = vec![];
+        for (i, c) in text.char_indices() {
+            let norm = i..i + c.len_utf8();
+            let orig = match normalized.convert_offsets(Range::Normalized(norm.clone())) {
+                Some(orig) => orig,
+                None => continue,
+            };
+            match spans.last_mut() {
+                Some((last_orig, last_norm)) if orig.start < last_orig.end || orig == (last_orig.end..last_orig.end) => {
+                    last_orig.end = last_orig.end.max(orig.end);
+                    last_norm.end = norm.end;
+                }
+                _ => spans.push((orig, norm)),
+            }
+        }
+
+        // keep the spans where the text differs, including removed parts of the original
+        let mut changes: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = vec![];
+        let (mut orig_pos, mut norm_pos) = (0, 0);
+        for (orig, norm) in spans {
+            if orig.start > orig_pos {
+                changes.push((orig_pos..orig.start, norm_pos..norm_pos));
+            }
+            if original[orig.clone()] != text[norm.clone()] {
+                changes.push((orig.clone(), norm.clone()));
+            }
+            orig_pos = orig_pos.max(orig.end);
+            norm_pos = norm.end;
+        }
+        if orig_pos < original.len() {
+            changes.push((orig_pos..original.len(), norm_pos..norm_pos));
+        }
+
+        // merge contiguous changes and convert to char offsets
+        let mut merged: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = vec![];
+        for (orig, norm) in changes {
+            match merged.last_mut() {
+                Some((last_orig, last_norm)) if last_orig.end == orig.start && last_norm.end == norm.start => {
+                    last_orig.end = orig.end;
+                    last_norm.end = norm.end;
+                }
+                _ => merged.push((orig, norm)),
+            }
+        }
+        let char_offset = |s: &str, byte: usize| s[..byte].chars().count();
+        let changes = merged
+            .into_iter()
+            .map(|(orig, norm)| {
+                (
+                    (char_offset(original, orig.start), char_offset(original, orig.end)),
+                    (char_offset(text, norm.start), char_offset(text, norm.end)),
+                )
+            })
+            .collect();
+
+        Ok((text.to_owned(), changes))
+    }
 }
 
 impl Normalizer for RbNormalizer {
@@ -113,20 +213,56 @@ impl RbNormalizer {
         setter!(self, Prepend, prepend, prepend)
     }
 
-    fn strip_left(&self) -> bool {
-        getter!(self, StripNormalizer, strip_left)
+    // Strip is upstream's normalizer unless chars are given
+    fn strip_settings(&self) -> RbResult<(bool, bool, Option<String>)> {
+        if let RbNormalizerTypeWrapper::Single(ref norm) = &self.normalizer {
+            match &*norm.read().unwrap() {
+                RbNormalizerWrapper::Wrapped(NormalizerWrapper::StripNormalizer(o)) => {
+                    return Ok((o.strip_left, o.strip_right, None));
+                }
+                RbNormalizerWrapper::Extra(RbExtraNormalizer::Strip(o)) => {
+                    return Ok((o.strip_left, o.strip_right, Some(o.chars.clone())));
+                }
+                _ => {}
+            }
+        }
+        Err(Error::new(exception::type_error(), "wrong normalizer type"))
+    }
+
+    fn set_strip_settings(&self, left: bool, right: bool, chars: Option<String>) {
+        if let RbNormalizerTypeWrapper::Single(ref norm) = &self.normalizer {
+            *norm.write().unwrap() = RbStrip::wrapper(left, right, chars);
+        }
     }
 
-    fn strip_set_left(&self, left: bool) {
-        setter!(self, StripNormalizer, strip_left, left)
+    fn strip_left(&self) -> RbResult<bool> {
+        Ok(self.strip_settings()?.0)
     }
 
-    fn strip_right(&self) -> bool {
-        getter!(self, StripNormalizer, strip_right)
+    fn strip_set_left(&self, left: bool) -> RbResult<()> {
+        let (_, right, chars) = self.strip_settings()?;
+        self.set_strip_settings(left, right, chars);
+        Ok(())
     }
 
-    fn strip_set_right(&self, right: bool) {
-        setter!(self, StripNormalizer, strip_right, right)
+    fn strip_right(&self) -> RbResult<bool> {
+        Ok(self.strip_settings()?.1)
+    }
+
+    fn strip_set_right(&self, right: bool) -> RbResult<()> {
+        let (left, _, chars) = self.strip_settings()?;
+        self.set_strip_settings(left, right, chars);
+        Ok(())
+    }
+
+    fn strip_chars(&self) -> RbResult<Option<String>> {
+        Ok(self.strip_settings()?.2)
+    }
+
+    fn strip_set_chars(&self, chars: Option<String>) -> RbResult<()> {
+        let (left, right, _) = self.strip_settings()?;
+        self.set_strip_settings(left, right, chars);
+        Ok(())
     }
 }
 
@@ -205,8 +341,19 @@ impl RbPrepend {
 pub struct RbStrip {}
 
 impl RbStrip {
-    pub fn new(left: bool, right: bool) -> RbNormalizer {
-        Strip::new(left, right).into()
+    pub fn new(left: bool, right: bool, chars: Option<String>) -> RbNormalizer {
+        RbNormalizer::new(Self::wrapper(left, right, chars).into())
+    }
+
+    fn wrapper(left: bool, right: bool, chars: Option<String>) -> RbNormalizerWrapper {
+        match chars {
+            Some(chars) => RbNormalizerWrapper::Extra(RbExtraNormalizer::Strip(StripChars {
+                strip_left: left,
+                strip_right: right,
+                chars,
+            })),
+            None => Strip::new(left, right).into(),
+        }
     }
 }
 
@@ -218,6 +365,22 @@ impl RbStripAccents {
     }
 }
 
+pub struct RbUnidecode {}
+
+impl RbUnidecode {
+    pub fn new() -> RbNormalizer {
+        RbNormalizer::new(RbNormalizerWrapper::Extra(RbExtraNormalizer::Unidecode).into())
+    }
+}
+
+pub struct RbUppercase {}
+
+impl RbUppercase {
+    pub fn new() -> RbNormalizer {
+        RbNormalizer::new(RbNormalizerWrapper::Extra(RbExtraNormalizer::Uppercase).into())
+    }
+}
+
 pub struct RbSequence {}
 
 impl RbSequence {
@@ -234,10 +397,99 @@ impl RbSequence {
     }
 }
 
+// Strip that removes the given chars instead of whitespace
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct StripChars {
+    strip_left: bool,
+    strip_right: bool,
+    chars: String,
+}
+
+impl Normalizer for StripChars {
+    fn normalize(&self, normalized: &mut NormalizedString) -> tk::Result<()> {
+        let chars: Vec<char> = normalized.get().chars().collect();
+        let stripped = |c: &&char| self.chars.contains(**c);
+
+        let leading = if self.strip_left {
+            chars.iter().take_while(stripped).count()
+        } else {
+            0
+        };
+        let trailing = if self.strip_right {
+            chars[leading..].iter().rev().take_while(stripped).count()
+        } else {
+            0
+        };
+
+        if leading > 0 || trailing > 0 {
+            let kept = &chars[leading..chars.len() - trailing];
+            let transformations = kept
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    if i == kept.len() - 1 {
+                        (*c, -(trailing as isize))
+                    } else {
+                        (*c, 0)
+                    }
+                })
+                .collect::<Vec<_>>();
+            normalized.transform(transformations, leading);
+        }
+        Ok(())
+    }
+}
+
+// normalizers implemented in this library rather than upstream
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum RbExtraNormalizer {
+    Strip(StripChars),
+    Unidecode,
+    Uppercase,
+}
+
+impl Normalizer for RbExtraNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) -> tk::Result<()> {
+        match self {
+            RbExtraNormalizer::Strip(inner) => inner.normalize(normalized)?,
+            RbExtraNormalizer::Unidecode => {
+                let mut transformations: Vec<(char, isize)> = Vec::with_capacity(normalized.len());
+                let mut initial_offset = 0;
+                for c in normalized.get().chars() {
+                    if c.is_ascii() {
+                        transformations.push((c, 0));
+                        continue;
+                    }
+                    // the first char replaces the original, the rest are insertions aligned to it
+                    let mut ascii = deunicode_char(c).unwrap_or("").chars();
+                    match ascii.next() {
+                        Some(first) => {
+                            transformations.push((first, 0));
+                            transformations.extend(ascii.map(|c| (c, 1)));
+                        }
+                        None => match transformations.last_mut() {
+                            Some((_, change)) => *change -= 1,
+                            None => initial_offset += 1,
+                        },
+                    }
+                }
+                normalized.transform(transformations, initial_offset);
+            }
+            RbExtraNormalizer::Uppercase => {
+                normalized.uppercase();
+            }
+        }
+        Ok(())
+    }
+}
+
+// Extra is tried first so Strip keeps its chars when loaded
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub(crate) enum RbNormalizerWrapper {
     // Custom(CustomNormalizer),
+    Extra(RbExtraNormalizer),
     Wrapped(NormalizerWrapper),
 }
 
@@ -248,6 +500,7 @@ impl Serialize for RbNormalizerWrapper {
     {
         match self {
             RbNormalizerWrapper::Wrapped(inner) => inner.serialize(serializer),
+            RbNormalizerWrapper::Extra(inner) => inner.serialize(serializer),
             // RbNormalizerWrapper::Custom(inner) => inner.serialize(serializer),
         }
     }
@@ -321,6 +574,7 @@ impl Normalizer for RbNormalizerWrapper {
     fn normalize(&self, normalized: &mut NormalizedString) -> tk::Result<()> {
         match self {
             RbNormalizerWrapper::Wrapped(inner) => inner.normalize(normalized),
+            RbNormalizerWrapper::Extra(inner) => inner.normalize(normalized),
             // RbNormalizerWrapper::Custom(inner) => inner.normalize(normalized),
         }
     }
@@ -405,6 +659,23 @@ unsafe impl TypedData for RbNormalizer {
                     }),
                     _ => todo!(),
                 },
+                RbNormalizerWrapper::Extra(extra) => match &extra {
+                    RbExtraNormalizer::Strip(_) => *memoize!(RClass: {
+                        let class: RClass = crate::normalizers().const_get("Strip").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraNormalizer::Unidecode => *memoize!(RClass: {
+                        let class: RClass = crate::normalizers().const_get("Unidecode").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraNormalizer::Uppercase => *memoize!(RClass: {
+                        let class: RClass = crate::normalizers().const_get("Uppercase").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                },
             },
         }
     }
@@ -413,6 +684,13 @@ unsafe impl TypedData for RbNormalizer {
 pub fn normalizers(module: &RModule) -> RbResult<()> {
     let normalizer = module.define_class("Normalizer", Default::default())?;
     normalizer.define_method("normalize_str", method!(RbNormalizer::normalize_str, 1))?;
+    normalizer.define_method("normalize_with_changes", method!(RbNormalizer::normalize_with_changes, 1))?;
+    normalizer.define_singleton_method("from_str", function!(RbNormalizer::from_str, 1))?;
+    normalizer.define_method("_to_s", method!(RbNormalizer::to_str, 1))?;
+    normalizer.define_method("==", method!(RbNormalizer::eq, 1))?;
+    normalizer.define_method("eql?", method!(RbNormalizer::eq, 1))?;
+    normalizer.define_method("hash", method!(RbNormalizer::hash, 0))?;
+    normalizer.define_method("inspect", method!(RbNormalizer::inspect, 0))?;
 
     let class = module.define_class("Sequence", normalizer)?;
     class.define_singleton_method("new", function!(RbSequence::new, 1))?;
@@ -455,14 +733,22 @@ pub fn normalizers(module: &RModule) -> RbResult<()> {
     class.define_method("prepend=", method!(RbNormalizer::prepend_set_prepend, 1))?;
 
     let class = module.define_class("Strip", normalizer)?;
-    class.define_singleton_method("_new", function!(RbStrip::new, 2))?;
+    class.define_singleton_method("_new", function!(RbStrip::new, 3))?;
     class.define_method("left", method!(RbNormalizer::strip_left, 0))?;
     class.define_method("left=", method!(RbNormalizer::strip_set_left, 1))?;
     class.define_method("right", method!(RbNormalizer::strip_right, 0))?;
     class.define_method("right=", method!(RbNormalizer::strip_set_right, 1))?;
+    class.define_method("chars", method!(RbNormalizer::strip_chars, 0))?;
+    class.define_method("chars=", method!(RbNormalizer::strip_set_chars, 1))?;
 
     let class = module.define_class("StripAccents", normalizer)?;
     class.define_singleton_method("new", function!(RbStripAccents::new, 0))?;
 
+    let class = module.define_class("Unidecode", normalizer)?;
+    class.define_singleton_method("new", function!(RbUnidecode::new, 0))?;
+
+    let class = module.define_class("Uppercase", normalizer)?;
+    class.define_singleton_method("new", function!(RbUppercase::new, 0))?;
+
     Ok(())
 }
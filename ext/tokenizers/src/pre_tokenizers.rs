@@ -1,11 +1,18 @@
+// Pre-tokenizers and options that upstream 0.13 doesn't have are implemented here as
+// RbExtraPreTokenizer variants. The ones adding an option to an upstream pre-tokenizer
+// serialize with its `type` and use the same Ruby class, but are only used when the
+// option is set, so everything else saves exactly like upstream. When loading, they
+// reject configs without the option, which then fall through to upstream.
+
 use std::sync::{Arc, RwLock};
 
 use magnus::typed_data::DataTypeBuilder;
 use magnus::{
-    function, memoize, method, Class, DataType, DataTypeFunctions, Module, Object,
-    RArray, RClass, RModule, TypedData,
+    exception, function, memoize, method, Class, DataType, DataTypeFunctions, Error, Module, Object,
+    RArray, RClass, RModule, Symbol, TypedData, Value,
 };
 
+use onig::Regex;
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -15,12 +22,15 @@ use tk::pre_tokenizers::delimiter::CharDelimiterSplit;
 use tk::pre_tokenizers::digits::Digits;
 use tk::pre_tokenizers::metaspace::Metaspace;
 use tk::pre_tokenizers::punctuation::Punctuation;
-use tk::pre_tokenizers::split::Split;
+use tk::pre_tokenizers::split::{Split, SplitPattern};
 use tk::pre_tokenizers::unicode_scripts::UnicodeScripts;
 use tk::pre_tokenizers::whitespace::{Whitespace, WhitespaceSplit};
 use tk::pre_tokenizers::PreTokenizerWrapper;
+use tk::normalizer::{Range, SplitDelimiterBehavior};
+use tk::pattern::Pattern;
 use tk::tokenizer::Offsets;
-use tk::{PreTokenizedString, PreTokenizer};
+use tk::{NormalizedString, OffsetReferential, OffsetType, PreTokenizedString, PreTokenizer};
+use unicode_script::{Script, UnicodeScript};
 
 use super::utils::*;
 use super::{RbError, RbResult};
@@ -32,34 +42,78 @@ pub struct RbPreTokenizer {
 }
 
 impl RbPreTokenizer {
-    fn pre_tokenize_str(&self, s: String) -> RbResult<Vec<(String, Offsets)>> {
-        let mut pretokenized = tk::tokenizer::PreTokenizedString::from(s);
+    fn pre_tokenize_str(&self, s: Value, offset_type: String) -> RbResult<Vec<(String, Offsets)>> {
+        let offset_type = match offset_type.as_str() {
+            "char" => OffsetType::Char,
+            "byte" => OffsetType::Byte,
+            _ => return Err(Error::new(exception::arg_error(), "offset_type must be :char or :byte")),
+        };
+
+        let mut pretokenized = tk::tokenizer::PreTokenizedString::from(utf8_string(s)?);
 
         self.pretok.pre_tokenize(&mut pretokenized).map_err(RbError::from)?;
 
         Ok(pretokenized
-            .get_splits(tk::OffsetReferential::Original, tk::OffsetType::Char)
+            .get_splits(tk::OffsetReferential::Original, offset_type)
             .into_iter()
             .map(|(s, o, _)| (s.to_owned(), o))
             .collect())
     }
+
+    fn from_str(json: String) -> RbResult<Self> {
+        serde_json::from_str(&json).map_err(|e| RbError::from(e.into()))
+    }
+
+    fn to_str(&self, pretty: bool) -> RbResult<String> {
+        if pretty {
+            serde_json::to_string_pretty(self)
+        } else {
+            serde_json::to_string(self)
+        }
+        .map_err(|e| RbError::from(e.into()))
+    }
+
+    fn eq(&self, other: Value) -> RbResult<bool> {
+        match other.try_convert::<&RbPreTokenizer>() {
+            Ok(other) => serialized_eq(self, other),
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn hash(&self) -> RbResult<i64> {
+        serialized_hash(self)
+    }
+
+    fn inspect(rb_self: Value) -> RbResult<String> {
+        let pretok: &RbPreTokenizer = rb_self.try_convert()?;
+        serialized_inspect(rb_self, pretok)
+    }
 }
 
-macro_rules! getter {
-    ($self: ident, $variant: ident, $($name: tt)+) => {{
+// raised when a method is called on a different kind of pre-tokenizer than it's defined for
+fn wrong_type() -> Error {
+    Error::new(exception::type_error(), "wrong pre-tokenizer type")
+}
+
+macro_rules! match_single {
+    ($self: ident, $($pattern: pat => $value: expr),+ $(,)?) => {{
         if let RbPreTokenizerTypeWrapper::Single(ref single) = &$self.pretok {
-            if let RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::$variant(ref pretok)) =
-                *single.read().unwrap() {
-                    pretok.$($name)+
-                } else {
-                    unreachable!()
-                }
+            match *single.read().unwrap() {
+                $($pattern => Ok($value),)+
+                _ => Err(wrong_type()),
+            }
         } else {
-            unreachable!()
+            Err(wrong_type())
         }
     }};
 }
 
+macro_rules! getter {
+    ($self: ident, $variant: ident, $($name: tt)+) => {
+        match_single!($self, RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::$variant(ref pretok)) => pretok.$($name)+)
+    };
+}
+
 macro_rules! setter {
     ($self: ident, $variant: ident, $name: ident, $value: expr) => {{
         if let RbPreTokenizerTypeWrapper::Single(ref single) = &$self.pretok {
@@ -81,13 +135,25 @@ macro_rules! setter {
     }};
 }
 
+macro_rules! extra_setter {
+    ($self: ident, $variant: ident, $name: ident, $value: expr) => {{
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &$self.pretok {
+            if let RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::$variant(ref mut pretok)) =
+                *single.write().unwrap()
+            {
+                pretok.$name = $value;
+            }
+        }
+    }};
+}
+
 impl RbPreTokenizer {
     #[allow(dead_code)]
     pub(crate) fn new(pretok: RbPreTokenizerTypeWrapper) -> Self {
         RbPreTokenizer { pretok }
     }
 
-    fn byte_level_add_prefix_space(&self) -> bool {
+    fn byte_level_add_prefix_space(&self) -> RbResult<bool> {
         getter!(self, ByteLevel, add_prefix_space)
     }
 
@@ -95,7 +161,7 @@ impl RbPreTokenizer {
         setter!(self, ByteLevel, add_prefix_space, add_prefix_space);
     }
 
-    fn byte_level_use_regex(&self) -> bool {
+    fn byte_level_use_regex(&self) -> RbResult<bool> {
         getter!(self, ByteLevel, use_regex)
     }
 
@@ -103,37 +169,220 @@ impl RbPreTokenizer {
         setter!(self, ByteLevel, use_regex, use_regex);
     }
 
-    fn char_delimiter_split_delimiter(&self) -> String {
-        getter!(self, Delimiter, delimiter.to_string())
+    fn char_delimiter_split_delimiter(&self) -> RbResult<String> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Delimiter(ref pretok)) => pretok.delimiter.to_string(),
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::CharDelimiterSplit(ref pretok)) => pretok.delimiter.clone(),
+        )
     }
 
-    fn char_delimiter_split_set_delimiter(&self, delimiter: char) {
-        setter!(self, Delimiter, delimiter, delimiter);
+    // the delimiter's length decides which pre-tokenizer is used, so replace it
+    fn char_delimiter_split_set_delimiter(&self, delimiter: String) -> RbResult<()> {
+        let wrapper = char_delimiter_wrapper(delimiter)?;
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &self.pretok {
+            *single.write().unwrap() = wrapper;
+        }
+        Ok(())
     }
 
-    fn digits_individual_digits(&self) -> bool {
-        getter!(self, Digits, individual_digits)
+    fn digits_individual_digits(&self) -> RbResult<bool> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Digits(ref pretok)) => pretok.individual_digits,
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Digits(ref pretok)) => pretok.individual_digits,
+        )
     }
 
-    fn digits_set_individual_digits(&self, individual_digits: bool) {
+    fn digits_set_individual_digits(&self, individual_digits: bool) -> RbResult<()> {
+        check_digits_options(individual_digits, self.digits_keep_separators()?)?;
         setter!(self, Digits, individual_digits, individual_digits);
+        extra_setter!(self, Digits, individual_digits, individual_digits);
+        Ok(())
     }
 
-    fn metaspace_add_prefix_space(&self) -> bool {
-        getter!(self, Metaspace, add_prefix_space)
+    fn digits_keep_separators(&self) -> RbResult<bool> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Digits(_)) => false,
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Digits(ref pretok)) => pretok.keep_separators,
+        )
+    }
+
+    // switches between upstream Digits and the extra one in place,
+    // so sequences holding this pre-tokenizer see the change
+    fn digits_set_keep_separators(&self, keep_separators: bool) -> RbResult<()> {
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &self.pretok {
+            let individual_digits = self.digits_individual_digits()?;
+            let first_only = self.digits_first_only()?;
+            *single.write().unwrap() = digits_wrapper(individual_digits, keep_separators, first_only)?;
+        }
+        Ok(())
+    }
+
+    fn digits_first_only(&self) -> RbResult<bool> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Digits(_)) => false,
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Digits(ref pretok)) => pretok.first_only,
+        )
+    }
+
+    fn digits_set_first_only(&self, first_only: bool) -> RbResult<()> {
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &self.pretok {
+            let individual_digits = self.digits_individual_digits()?;
+            let keep_separators = self.digits_keep_separators()?;
+            *single.write().unwrap() = digits_wrapper(individual_digits, keep_separators, first_only)?;
+        }
+        Ok(())
+    }
+
+    fn metaspace_add_prefix_space(&self) -> RbResult<bool> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Metaspace(ref pretok)) => pretok.add_prefix_space,
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Metaspace(ref pretok)) => pretok.add_prefix_space,
+        )
     }
 
     fn metaspace_set_add_prefix_space(&self, add_prefix_space: bool) {
         setter!(self, Metaspace, add_prefix_space, add_prefix_space);
+        extra_setter!(self, Metaspace, add_prefix_space, add_prefix_space);
     }
 
-    fn metaspace_replacement(&self) -> String {
-        getter!(self, Metaspace, get_replacement().to_string())
+    fn metaspace_replacement(&self) -> RbResult<String> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Metaspace(ref pretok)) => pretok.get_replacement().to_string(),
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Metaspace(ref pretok)) => pretok.replacement.to_string(),
+        )
     }
 
-    fn metaspace_set_replacement(&self, replacement: char) {
+    fn metaspace_set_replacement(&self, replacement: String) -> RbResult<()> {
+        let replacement = replacement_char(&replacement)?;
         setter!(self, Metaspace, @set_replacement, replacement);
+        extra_setter!(self, Metaspace, replacement, replacement);
+        Ok(())
+    }
+
+    fn metaspace_split(&self) -> RbResult<bool> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Metaspace(_)) => true,
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Metaspace(_)) => false,
+        )
+    }
+
+    // switches between upstream Metaspace and the extra one in place,
+    // so sequences holding this pre-tokenizer see the change
+    fn metaspace_set_split(&self, split: bool) -> RbResult<()> {
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &self.pretok {
+            let replacement = replacement_char(&self.metaspace_replacement()?)?;
+            let add_prefix_space = self.metaspace_add_prefix_space()?;
+            *single.write().unwrap() = metaspace_wrapper(replacement, add_prefix_space, split);
+        }
+        Ok(())
+    }
+
+    fn sequence_length(&self) -> RbResult<usize> {
+        if let RbPreTokenizerTypeWrapper::Sequence(seq) = &self.pretok {
+            Ok(seq.len())
+        } else {
+            Err(wrong_type())
+        }
+    }
+
+    // shares the element with the sequence, so setters update the sequence too
+    fn sequence_get(&self, index: isize) -> RbResult<RbPreTokenizer> {
+        if let RbPreTokenizerTypeWrapper::Sequence(seq) = &self.pretok {
+            let i = if index < 0 { index + seq.len() as isize } else { index };
+            if i < 0 || i as usize >= seq.len() {
+                return Err(Error::new(
+                    exception::index_error(),
+                    format!("index {} outside of sequence", index),
+                ));
+            }
+            Ok(RbPreTokenizer::new(RbPreTokenizerTypeWrapper::Single(seq[i as usize].clone())))
+        } else {
+            Err(wrong_type())
+        }
+    }
+
+    // runs the pre-tokenizer itself so the boundaries match exactly
+    fn unicode_scripts_scripts(&self, s: String) -> RbResult<Vec<(String, String)>> {
+        let mut pretokenized = PreTokenizedString::from(s);
+
+        self.pretok.pre_tokenize(&mut pretokenized).map_err(RbError::from)?;
+
+        Ok(pretokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Char)
+            .into_iter()
+            .map(|(s, _, _)| (s.to_owned(), script_name(s).to_owned()))
+            .collect())
+    }
+
+    fn punctuation_behavior(&self) -> RbResult<&'static str> {
+        if let RbPreTokenizerTypeWrapper::Single(ref single) = &self.pretok {
+            if let RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Punctuation(ref pretok)) = *single.read().unwrap() {
+                return Ok(RbSplitDelimiterBehavior(pretok.behavior).as_str());
+            }
+        }
+        let punctuation = getter!(self, Punctuation, clone())?;
+        let settings: PunctuationSettings = serde_json::to_value(punctuation)
+            .and_then(serde_json::from_value)
+            .map_err(|e| RbError::from(e.into()))?;
+        Ok(RbSplitDelimiterBehavior(settings.behavior).as_str())
+    }
+
+    fn punctuation_scope(&self) -> RbResult<Symbol> {
+        match_single!(
+            self,
+            RbPreTokenizerWrapper::Wrapped(PreTokenizerWrapper::Punctuation(_)) => Symbol::new("all"),
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Punctuation(ref pretok)) => Symbol::new(pretok.scope.as_str()),
+        )
+    }
+
+    // upstream keeps these fields private, so read them back from the serialized form
+    fn split_settings(&self) -> RbResult<SplitSettings> {
+        let split = getter!(self, Split, clone())?;
+        serde_json::to_value(split)
+            .and_then(serde_json::from_value)
+            .map_err(|e| RbError::from(e.into()))
+    }
+
+    fn split_pattern(&self) -> RbResult<String> {
+        Ok(match self.split_settings()?.pattern {
+            SplitPattern::String(s) => s,
+            SplitPattern::Regex(r) => r,
+        })
     }
+
+    fn split_pattern_kind(&self) -> RbResult<Symbol> {
+        Ok(match self.split_settings()?.pattern {
+            SplitPattern::String(_) => Symbol::new("string"),
+            SplitPattern::Regex(_) => Symbol::new("regex"),
+        })
+    }
+
+    fn split_behavior(&self) -> RbResult<&'static str> {
+        Ok(RbSplitDelimiterBehavior(self.split_settings()?.behavior).as_str())
+    }
+
+    fn split_invert(&self) -> RbResult<bool> {
+        Ok(self.split_settings()?.invert)
+    }
+}
+
+#[derive(Deserialize)]
+struct PunctuationSettings {
+    behavior: SplitDelimiterBehavior,
+}
+
+#[derive(Deserialize)]
+struct SplitSettings {
+    pattern: SplitPattern,
+    behavior: SplitDelimiterBehavior,
+    invert: bool,
 }
 
 impl PreTokenizer for RbPreTokenizer {
@@ -163,35 +412,105 @@ impl RbByteLevel {
 pub struct RbCharDelimiterSplit {}
 
 impl RbCharDelimiterSplit {
-    pub fn new(delimiter: char) -> RbPreTokenizer {
-        CharDelimiterSplit::new(delimiter).into()
+    pub fn new(delimiter: String) -> RbResult<RbPreTokenizer> {
+        Ok(RbPreTokenizer::new(char_delimiter_wrapper(delimiter)?.into()))
+    }
+}
+
+// single chars use the faster upstream CharDelimiterSplit
+fn char_delimiter_wrapper(delimiter: String) -> RbResult<RbPreTokenizerWrapper> {
+    let mut chars = delimiter.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Err(Error::new(exception::arg_error(), "delimiter must not be empty")),
+        (Some(c), None) => Ok(CharDelimiterSplit::new(c).into()),
+        _ => StringDelimiterSplit::new(delimiter)
+            .map(|v| RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::CharDelimiterSplit(v)))
+            .map_err(RbError::from),
     }
 }
 
 pub struct RbDigits {}
 
 impl RbDigits {
-    fn new(individual_digits: bool) -> RbPreTokenizer {
-        Digits::new(individual_digits).into()
+    fn new(individual_digits: bool, keep_separators: bool, first_only: bool) -> RbResult<RbPreTokenizer> {
+        Ok(RbPreTokenizer::new(digits_wrapper(individual_digits, keep_separators, first_only)?.into()))
     }
 }
 
+fn digits_wrapper(individual_digits: bool, keep_separators: bool, first_only: bool) -> RbResult<RbPreTokenizerWrapper> {
+    check_digits_options(individual_digits, keep_separators)?;
+    Ok(if keep_separators || first_only {
+        RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Digits(SeparatedDigits {
+            individual_digits,
+            keep_separators,
+            first_only,
+        }))
+    } else {
+        RbPreTokenizerWrapper::Wrapped(Digits::new(individual_digits).into())
+    })
+}
+
+fn check_digits_options(individual_digits: bool, keep_separators: bool) -> RbResult<()> {
+    if individual_digits && keep_separators {
+        return Err(Error::new(exception::arg_error(), DIGITS_OPTIONS_ERROR));
+    }
+    Ok(())
+}
+
 pub struct RbMetaspace {}
 
 impl RbMetaspace {
     fn new(
-        replacement: char,
+        replacement: String,
         add_prefix_space: bool,
-    ) -> RbPreTokenizer {
-        Metaspace::new(replacement, add_prefix_space).into()
+        split: bool,
+    ) -> RbResult<RbPreTokenizer> {
+        let replacement = replacement_char(&replacement)?;
+        Ok(RbPreTokenizer::new(metaspace_wrapper(replacement, add_prefix_space, split).into()))
+    }
+}
+
+// only `split: false` needs the extra Metaspace, so the rest serialize like upstream
+fn metaspace_wrapper(replacement: char, add_prefix_space: bool, split: bool) -> RbPreTokenizerWrapper {
+    if split {
+        RbPreTokenizerWrapper::Wrapped(Metaspace::new(replacement, add_prefix_space).into())
+    } else {
+        RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Metaspace(UnsplitMetaspace {
+            replacement,
+            add_prefix_space,
+            split: false,
+        }))
+    }
+}
+
+fn replacement_char(replacement: &str) -> RbResult<char> {
+    let mut chars = replacement.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(Error::new(
+            exception::arg_error(),
+            format!("replacement must be exactly one character, got {:?}", replacement),
+        )),
     }
 }
 
 pub struct RbPunctuation {}
 
 impl RbPunctuation {
-    pub fn new(behavior: RbSplitDelimiterBehavior) -> RbResult<RbPreTokenizer> {
-        Ok(Punctuation::new(behavior.into()).into())
+    pub fn new(behavior: RbSplitDelimiterBehavior, scope: String) -> RbResult<RbPreTokenizer> {
+        let scope = match scope.as_str() {
+            "all" => return Ok(Punctuation::new(behavior.into()).into()),
+            "ascii" => PunctuationScope::Ascii,
+            "unicode" => PunctuationScope::Unicode,
+            _ => {
+                return Err(Error::new(
+                    exception::arg_error(),
+                    "Wrong value for scope, expected one of: `ascii, unicode, all`",
+                ))
+            }
+        };
+        let punctuation = ScopedPunctuation::new(behavior.into(), scope).map_err(RbError::from)?;
+        Ok(RbPreTokenizer::new(RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::Punctuation(punctuation)).into()))
     }
 }
 
@@ -211,6 +530,18 @@ impl RbUnicodeScripts {
     }
 }
 
+// same adjustments as upstream UnicodeScripts, where spaces join any script
+// and kana is grouped with Han
+fn script_name(s: &str) -> &'static str {
+    s.chars()
+        .find(|c| *c != ' ')
+        .map_or("Any", |c| match c.script() {
+            _ if c == '\u{30FC}' => "Han",
+            Script::Hiragana | Script::Katakana => "Han",
+            script => script.full_name(),
+        })
+}
+
 pub struct RbWhitespace {}
 
 impl RbWhitespace {
@@ -253,10 +584,454 @@ impl RbSequence {
     }
 }
 
+pub struct RbFixedLength {}
+
+impl RbFixedLength {
+    pub fn new(length: usize) -> RbResult<RbPreTokenizer> {
+        let fixed_length = FixedLength::new(length)
+            .map_err(|e| Error::new(exception::arg_error(), e))?;
+        Ok(RbPreTokenizer::new(
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::FixedLength(fixed_length)).into(),
+        ))
+    }
+}
+
+pub struct RbMergeAdjacent {}
+
+impl RbMergeAdjacent {
+    pub fn new(pattern: String, pretokenizer: &RbPreTokenizer) -> RbResult<RbPreTokenizer> {
+        let merge_adjacent = MergeAdjacent::new(pattern, pretokenizer.pretok.clone())
+            .map_err(|e| RbError::from(e.into()))?;
+        Ok(RbPreTokenizer::new(
+            RbPreTokenizerWrapper::Extra(RbExtraPreTokenizer::MergeAdjacent(merge_adjacent)).into(),
+        ))
+    }
+}
+
+// splits can't be joined once created, so this runs the wrapped pre-tokenizer itself
+// and slices runs of adjacent matching splits from the input instead
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "MergeAdjacentDeserializer")]
+pub(crate) struct MergeAdjacent {
+    pattern: String,
+    pretokenizer: RbPreTokenizerTypeWrapper,
+    #[serde(skip_serializing)]
+    regex: Arc<Regex>,
+}
+
+impl MergeAdjacent {
+    fn new(pattern: String, pretokenizer: RbPreTokenizerTypeWrapper) -> Result<Self, onig::Error> {
+        let regex = Regex::new(&format!(r"\A(?:{})\z", pattern))?;
+        Ok(Self {
+            pattern,
+            pretokenizer,
+            regex: Arc::new(regex),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct MergeAdjacentDeserializer {
+    pattern: String,
+    pretokenizer: RbPreTokenizerTypeWrapper,
+}
+
+impl TryFrom<MergeAdjacentDeserializer> for MergeAdjacent {
+    type Error = onig::Error;
+
+    fn try_from(v: MergeAdjacentDeserializer) -> Result<Self, Self::Error> {
+        MergeAdjacent::new(v.pattern, v.pretokenizer)
+    }
+}
+
+impl PreTokenizer for MergeAdjacent {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        pretokenized.split(|_, normalized| {
+            let mut inner = PreTokenizedString::from(normalized.clone());
+            self.pretokenizer.pre_tokenize(&mut inner)?;
+
+            let mut runs: Vec<(Offsets, bool, usize)> = vec![];
+            for (split, (start, end), _) in inner.get_splits(OffsetReferential::Original, OffsetType::Byte) {
+                let matches = self.regex.is_match(split);
+                if let Some((offsets, true, count)) = runs.last_mut() {
+                    if matches && offsets.1 == start {
+                        offsets.1 = end;
+                        *count += 1;
+                        continue;
+                    }
+                }
+                runs.push(((start, end), matches, 1));
+            }
+
+            // take the inner splits as is, so unmerged ones keep any changes made by the pre-tokenizer
+            let mut splits = vec![];
+            inner.split(|_, split| {
+                splits.push(split);
+                Ok(Vec::<NormalizedString>::new())
+            })?;
+
+            let shift = normalized.offsets_original().0;
+            let mut splits = splits.into_iter();
+            let mut merged = Vec::with_capacity(runs.len());
+            for ((start, end), _, count) in runs {
+                if count == 1 {
+                    merged.extend(splits.next());
+                } else {
+                    splits.nth(count - 1);
+                    merged.extend(normalized.slice(Range::Original(start - shift..end - shift)));
+                }
+            }
+            Ok(merged)
+        })
+    }
+}
+
+// splits into chunks of `length` chars, the last of which may be shorter
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "FixedLengthDeserializer")]
+pub(crate) struct FixedLength {
+    length: usize,
+}
+
+impl FixedLength {
+    fn new(length: usize) -> Result<Self, &'static str> {
+        if length == 0 {
+            return Err("length must be greater than 0");
+        }
+        Ok(Self { length })
+    }
+}
+
+#[derive(Deserialize)]
+struct FixedLengthDeserializer {
+    length: usize,
+}
+
+impl TryFrom<FixedLengthDeserializer> for FixedLength {
+    type Error = &'static str;
+
+    fn try_from(v: FixedLengthDeserializer) -> Result<Self, Self::Error> {
+        FixedLength::new(v.length)
+    }
+}
+
+impl PreTokenizer for FixedLength {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        pretokenized.split(|_, normalized| {
+            let text = normalized.get();
+            let mut boundaries: Vec<usize> = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .step_by(self.length)
+                .collect();
+            boundaries.push(text.len());
+
+            Ok(boundaries
+                .windows(2)
+                .filter_map(|w| normalized.slice(Range::Normalized(w[0]..w[1])))
+                .collect::<Vec<_>>())
+        })
+    }
+}
+
+// Metaspace with `split: false`, which replaces spaces without splitting on them
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "UnsplitMetaspaceDeserializer")]
+pub(crate) struct UnsplitMetaspace {
+    replacement: char,
+    add_prefix_space: bool,
+    split: bool,
+}
+
+#[derive(Deserialize)]
+struct UnsplitMetaspaceDeserializer {
+    replacement: char,
+    add_prefix_space: bool,
+    split: bool,
+}
+
+impl TryFrom<UnsplitMetaspaceDeserializer> for UnsplitMetaspace {
+    type Error = &'static str;
+
+    fn try_from(v: UnsplitMetaspaceDeserializer) -> Result<Self, Self::Error> {
+        // split metaspaces are left for upstream Metaspace
+        if v.split {
+            return Err("split must be false");
+        }
+        Ok(Self {
+            replacement: v.replacement,
+            add_prefix_space: v.add_prefix_space,
+            split: false,
+        })
+    }
+}
+
+impl PreTokenizer for UnsplitMetaspace {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        let str_rep = self.replacement.to_string();
+        pretokenized.split(|_, mut normalized| {
+            normalized.replace(' ', &str_rep)?;
+            if self.add_prefix_space && !normalized.get().starts_with(self.replacement) {
+                normalized.prepend(&str_rep);
+            }
+            Ok(vec![normalized])
+        })
+    }
+}
+
+// Digits that can keep numbers like 1,234.56 together and only split off the first number
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SeparatedDigitsDeserializer")]
+pub(crate) struct SeparatedDigits {
+    individual_digits: bool,
+    keep_separators: bool,
+    first_only: bool,
+}
+
+const DIGITS_OPTIONS_ERROR: &str = "keep_separators can't be used with individual_digits";
+
+#[derive(Deserialize)]
+struct SeparatedDigitsDeserializer {
+    individual_digits: bool,
+    keep_separators: bool,
+    #[serde(default)]
+    first_only: bool,
+}
+
+impl TryFrom<SeparatedDigitsDeserializer> for SeparatedDigits {
+    type Error = &'static str;
+
+    fn try_from(v: SeparatedDigitsDeserializer) -> Result<Self, Self::Error> {
+        // left for upstream Digits, like the pre-tokenizers from digits_wrapper
+        if v.individual_digits && v.keep_separators {
+            return Err(DIGITS_OPTIONS_ERROR);
+        }
+        Ok(Self {
+            individual_digits: v.individual_digits,
+            keep_separators: v.keep_separators,
+            first_only: v.first_only,
+        })
+    }
+}
+
+impl PreTokenizer for SeparatedDigits {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        let separated = self.keep_separators;
+        if !self.first_only {
+            if !separated {
+                return Digits::new(self.individual_digits).pre_tokenize(pretokenized);
+            }
+            return pretokenized.split(|_, normalized| normalized.split(SeparatedNumber, SplitDelimiterBehavior::Isolated));
+        }
+
+        // same behaviors as upstream Digits
+        pretokenized.split(|_, normalized| {
+            if separated {
+                normalized.split(FirstRun(SeparatedNumber), SplitDelimiterBehavior::Isolated)
+            } else if self.individual_digits {
+                normalized.split(FirstRun(char::is_numeric), SplitDelimiterBehavior::Isolated)
+            } else {
+                normalized.split(FirstRun(char::is_numeric), SplitDelimiterBehavior::Contiguous)
+            }
+        })
+    }
+}
+
+// only keeps the first run of adjacent matches, leaving the rest as a single non-match
+struct FirstRun<P>(P);
+
+impl<P: Pattern> Pattern for FirstRun<P> {
+    fn find_matches(&self, inside: &str) -> tk::Result<Vec<(Offsets, bool)>> {
+        let mut matches = self.0.find_matches(inside)?;
+        if let Some(first) = matches.iter().position(|(_, is_match)| *is_match) {
+            let mut end = first + 1;
+            while end < matches.len() && matches[end].1 && matches[end].0 .0 == matches[end - 1].0 .1 {
+                end += 1;
+            }
+            if end < matches.len() {
+                let rest = (matches[end].0 .0, inside.len());
+                matches.truncate(end);
+                matches.push((rest, false));
+            }
+        }
+        Ok(matches)
+    }
+}
+
+// matches runs of digits along with any `.` or `,` between two digits
+struct SeparatedNumber;
+
+impl Pattern for SeparatedNumber {
+    fn find_matches(&self, inside: &str) -> tk::Result<Vec<(Offsets, bool)>> {
+        if inside.is_empty() {
+            return Ok(vec![((0, 0), false)]);
+        }
+
+        let chars: Vec<(usize, char)> = inside.char_indices().collect();
+        let is_separator = |i: usize| {
+            matches!(chars[i].1, '.' | ',') && chars.get(i + 1).map_or(false, |(_, c)| c.is_numeric())
+        };
+
+        let mut matches = vec![];
+        let mut prev = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            if !chars[i].1.is_numeric() {
+                i += 1;
+                continue;
+            }
+            let start = chars[i].0;
+            i += 1;
+            while i < chars.len() && (chars[i].1.is_numeric() || is_separator(i)) {
+                i += 1;
+            }
+            let end = chars.get(i).map_or(inside.len(), |(offset, _)| *offset);
+            if start > prev {
+                matches.push(((prev, start), false));
+            }
+            matches.push(((start, end), true));
+            prev = end;
+        }
+        if prev < inside.len() {
+            matches.push(((prev, inside.len()), false));
+        }
+        Ok(matches)
+    }
+}
+
+// CharDelimiterSplit on a delimiter longer than one char
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "StringDelimiterSplitDeserializer")]
+pub(crate) struct StringDelimiterSplit {
+    delimiter: String,
+    #[serde(skip_serializing)]
+    split: Split,
+}
+
+impl StringDelimiterSplit {
+    fn new(delimiter: String) -> tk::Result<Self> {
+        let pattern = SplitPattern::String(delimiter.clone());
+        Ok(Self {
+            delimiter,
+            split: Split::new(pattern, SplitDelimiterBehavior::Removed, false)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct StringDelimiterSplitDeserializer {
+    delimiter: String,
+}
+
+impl TryFrom<StringDelimiterSplitDeserializer> for StringDelimiterSplit {
+    type Error = tk::Error;
+
+    fn try_from(v: StringDelimiterSplitDeserializer) -> Result<Self, Self::Error> {
+        // single chars are left for upstream CharDelimiterSplit
+        if v.delimiter.chars().count() < 2 {
+            return Err("delimiter must be longer than one character".into());
+        }
+        StringDelimiterSplit::new(v.delimiter)
+    }
+}
+
+impl PreTokenizer for StringDelimiterSplit {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        self.split.pre_tokenize(pretokenized)
+    }
+}
+
+// Punctuation that only splits on ASCII or non-ASCII punctuation
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ScopedPunctuationDeserializer")]
+pub(crate) struct ScopedPunctuation {
+    behavior: SplitDelimiterBehavior,
+    scope: PunctuationScope,
+    #[serde(skip_serializing)]
+    split: Split,
+}
+
+impl ScopedPunctuation {
+    fn new(behavior: SplitDelimiterBehavior, scope: PunctuationScope) -> tk::Result<Self> {
+        let pattern = match scope {
+            PunctuationScope::Ascii => r"[!-/:-@\[-`{-~]",
+            PunctuationScope::Unicode => r"[\p{P}&&[^\x00-\x7F]]",
+        };
+        Ok(Self {
+            behavior,
+            scope,
+            split: Split::new(SplitPattern::Regex(pattern.to_string()), behavior, false)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PunctuationScope {
+    Ascii,
+    Unicode,
+}
+
+impl PunctuationScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PunctuationScope::Ascii => "ascii",
+            PunctuationScope::Unicode => "unicode",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ScopedPunctuationDeserializer {
+    behavior: SplitDelimiterBehavior,
+    scope: PunctuationScope,
+}
+
+impl TryFrom<ScopedPunctuationDeserializer> for ScopedPunctuation {
+    type Error = tk::Error;
+
+    fn try_from(v: ScopedPunctuationDeserializer) -> Result<Self, Self::Error> {
+        ScopedPunctuation::new(v.behavior, v.scope)
+    }
+}
+
+impl PreTokenizer for ScopedPunctuation {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        self.split.pre_tokenize(pretokenized)
+    }
+}
+
+// see the note at the top of the file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum RbExtraPreTokenizer {
+    CharDelimiterSplit(StringDelimiterSplit),
+    Digits(SeparatedDigits),
+    FixedLength(FixedLength),
+    MergeAdjacent(MergeAdjacent),
+    Metaspace(UnsplitMetaspace),
+    Punctuation(ScopedPunctuation),
+}
+
+impl PreTokenizer for RbExtraPreTokenizer {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> tk::Result<()> {
+        match self {
+            RbExtraPreTokenizer::CharDelimiterSplit(inner) => inner.pre_tokenize(pretokenized),
+            RbExtraPreTokenizer::Digits(inner) => inner.pre_tokenize(pretokenized),
+            RbExtraPreTokenizer::FixedLength(inner) => inner.pre_tokenize(pretokenized),
+            RbExtraPreTokenizer::MergeAdjacent(inner) => inner.pre_tokenize(pretokenized),
+            RbExtraPreTokenizer::Metaspace(inner) => inner.pre_tokenize(pretokenized),
+            RbExtraPreTokenizer::Punctuation(inner) => inner.pre_tokenize(pretokenized),
+        }
+    }
+}
+
+// Extra is tried first, since its variants share type names with upstream
 #[derive(Clone, Deserialize)]
 #[serde(untagged)]
 pub(crate) enum RbPreTokenizerWrapper {
     // Custom(CustomPreTokenizer),
+    Extra(RbExtraPreTokenizer),
     Wrapped(PreTokenizerWrapper),
 }
 
@@ -267,6 +1042,7 @@ impl Serialize for RbPreTokenizerWrapper {
     {
         match self {
             RbPreTokenizerWrapper::Wrapped(inner) => inner.serialize(serializer),
+            RbPreTokenizerWrapper::Extra(inner) => inner.serialize(serializer),
             // RbPreTokenizerWrapper::Custom(inner) => inner.serialize(serializer),
         }
     }
@@ -340,6 +1116,7 @@ impl PreTokenizer for RbPreTokenizerWrapper {
     fn pre_tokenize(&self, pretok: &mut PreTokenizedString) -> tk::Result<()> {
         match self {
             RbPreTokenizerWrapper::Wrapped(inner) => inner.pre_tokenize(pretok),
+            RbPreTokenizerWrapper::Extra(inner) => inner.pre_tokenize(pretok),
             // RbPreTokenizerWrapper::Custom(inner) => inner.pre_tokenize(pretok),
         }
     }
@@ -419,6 +1196,38 @@ unsafe impl TypedData for RbPreTokenizer {
                     }),
                     _ => todo!(),
                 },
+                RbPreTokenizerWrapper::Extra(extra) => match &extra {
+                    RbExtraPreTokenizer::CharDelimiterSplit(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("CharDelimiterSplit").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraPreTokenizer::Digits(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("Digits").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraPreTokenizer::FixedLength(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("FixedLength").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraPreTokenizer::MergeAdjacent(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("MergeAdjacent").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraPreTokenizer::Metaspace(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("Metaspace").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                    RbExtraPreTokenizer::Punctuation(_) => *memoize!(RClass: {
+                        let class: RClass = crate::pre_tokenizers().const_get("Punctuation").unwrap();
+                        class.undef_alloc_func();
+                        class
+                    }),
+                },
             },
         }
     }
@@ -426,10 +1235,18 @@ unsafe impl TypedData for RbPreTokenizer {
 
 pub fn pre_tokenizers(module: &RModule) -> RbResult<()> {
     let pre_tokenizer = module.define_class("PreTokenizer", Default::default())?;
-    pre_tokenizer.define_method("pre_tokenize_str", method!(RbPreTokenizer::pre_tokenize_str, 1))?;
+    pre_tokenizer.define_method("_pre_tokenize_str", method!(RbPreTokenizer::pre_tokenize_str, 2))?;
+    pre_tokenizer.define_singleton_method("from_str", function!(RbPreTokenizer::from_str, 1))?;
+    pre_tokenizer.define_method("_to_s", method!(RbPreTokenizer::to_str, 1))?;
+    pre_tokenizer.define_method("==", method!(RbPreTokenizer::eq, 1))?;
+    pre_tokenizer.define_method("eql?", method!(RbPreTokenizer::eq, 1))?;
+    pre_tokenizer.define_method("hash", method!(RbPreTokenizer::hash, 0))?;
+    pre_tokenizer.define_method("inspect", method!(RbPreTokenizer::inspect, 0))?;
 
     let class = module.define_class("Sequence", pre_tokenizer)?;
     class.define_singleton_method("new", function!(RbSequence::new, 1))?;
+    class.define_method("length", method!(RbPreTokenizer::sequence_length, 0))?;
+    class.define_method("[]", method!(RbPreTokenizer::sequence_get, 1))?;
 
     let class = module.define_class("BertPreTokenizer", pre_tokenizer)?;
     class.define_singleton_method("new", function!(RbBertPreTokenizer::new, 0))?;
@@ -448,25 +1265,44 @@ pub fn pre_tokenizers(module: &RModule) -> RbResult<()> {
     class.define_method("delimiter=", method!(RbPreTokenizer::char_delimiter_split_set_delimiter, 1))?;
 
     let class = module.define_class("Digits", pre_tokenizer)?;
-    class.define_singleton_method("_new", function!(RbDigits::new, 1))?;
+    class.define_singleton_method("_new", function!(RbDigits::new, 3))?;
     class.define_method("individual_digits", method!(RbPreTokenizer::digits_individual_digits, 0))?;
     class.define_method("individual_digits=", method!(RbPreTokenizer::digits_set_individual_digits, 1))?;
+    class.define_method("keep_separators", method!(RbPreTokenizer::digits_keep_separators, 0))?;
+    class.define_method("keep_separators=", method!(RbPreTokenizer::digits_set_keep_separators, 1))?;
+    class.define_method("first_only", method!(RbPreTokenizer::digits_first_only, 0))?;
+    class.define_method("first_only=", method!(RbPreTokenizer::digits_set_first_only, 1))?;
+
+    let class = module.define_class("FixedLength", pre_tokenizer)?;
+    class.define_singleton_method("new", function!(RbFixedLength::new, 1))?;
+
+    let class = module.define_class("MergeAdjacent", pre_tokenizer)?;
+    class.define_singleton_method("new", function!(RbMergeAdjacent::new, 2))?;
 
     let class = module.define_class("Metaspace", pre_tokenizer)?;
-    class.define_singleton_method("_new", function!(RbMetaspace::new, 2))?;
+    class.define_singleton_method("_new", function!(RbMetaspace::new, 3))?;
     class.define_method("add_prefix_space", method!(RbPreTokenizer::metaspace_add_prefix_space, 0))?;
     class.define_method("add_prefix_space=", method!(RbPreTokenizer::metaspace_set_add_prefix_space, 1))?;
     class.define_method("replacement", method!(RbPreTokenizer::metaspace_replacement, 0))?;
     class.define_method("replacement=", method!(RbPreTokenizer::metaspace_set_replacement, 1))?;
+    class.define_method("split", method!(RbPreTokenizer::metaspace_split, 0))?;
+    class.define_method("split=", method!(RbPreTokenizer::metaspace_set_split, 1))?;
 
     let class = module.define_class("Punctuation", pre_tokenizer)?;
-    class.define_singleton_method("_new", function!(RbPunctuation::new, 1))?;
+    class.define_singleton_method("_new", function!(RbPunctuation::new, 2))?;
+    class.define_method("behavior", method!(RbPreTokenizer::punctuation_behavior, 0))?;
+    class.define_method("scope", method!(RbPreTokenizer::punctuation_scope, 0))?;
 
     let class = module.define_class("Split", pre_tokenizer)?;
     class.define_singleton_method("_new", function!(RbSplit::new, 3))?;
+    class.define_method("pattern", method!(RbPreTokenizer::split_pattern, 0))?;
+    class.define_method("pattern_kind", method!(RbPreTokenizer::split_pattern_kind, 0))?;
+    class.define_method("behavior", method!(RbPreTokenizer::split_behavior, 0))?;
+    class.define_method("invert", method!(RbPreTokenizer::split_invert, 0))?;
 
     let class = module.define_class("UnicodeScripts", pre_tokenizer)?;
     class.define_singleton_method("new", function!(RbUnicodeScripts::new, 0))?;
+    class.define_method("scripts", method!(RbPreTokenizer::unicode_scripts_scripts, 1))?;
 
     let class = module.define_class("Whitespace", pre_tokenizer)?;
     class.define_singleton_method("new", function!(RbWhitespace::new, 0))?;
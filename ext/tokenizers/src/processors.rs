@@ -2,13 +2,14 @@ use std::sync::Arc;
 
 use magnus::typed_data::DataTypeBuilder;
 use magnus::{
-    function, memoize, Class, DataType, DataTypeFunctions, Module, Object, RClass, RModule,
-    TryConvert, TypedData, Value,
+    function, memoize, Class, DataType, DataTypeFunctions, Module, Object, RArray, RClass,
+    RModule, TryConvert, TypedData, Value,
 };
 use serde::{Deserialize, Serialize};
 use tk::processors::bert::BertProcessing;
 use tk::processors::byte_level::ByteLevel;
 use tk::processors::roberta::RobertaProcessing;
+use tk::processors::sequence::Sequence;
 use tk::processors::template::{SpecialToken, Template};
 use tk::processors::PostProcessorWrapper;
 use tk::{Encoding, PostProcessor};
@@ -126,6 +127,19 @@ impl RbRobertaProcessing {
     }
 }
 
+pub struct RbSequence {}
+
+impl RbSequence {
+    fn new(processors: RArray) -> RbResult<RbPostProcessor> {
+        let mut sequence = Vec::with_capacity(processors.len());
+        for p in processors.each() {
+            let processor: &RbPostProcessor = p?.try_convert()?;
+            sequence.push((*processor.processor).clone());
+        }
+        Ok(RbPostProcessor::new(Arc::new(Sequence::new(sequence).into())))
+    }
+}
+
 pub struct RbTemplateProcessing {}
 
 impl RbTemplateProcessing {
@@ -181,12 +195,16 @@ unsafe impl TypedData for RbPostProcessor {
                 class.undef_alloc_func();
                 class
             }),
+            PostProcessorWrapper::Sequence(_) => *memoize!(RClass: {
+                let class: RClass = crate::processors().const_get("Sequence").unwrap();
+                class.undef_alloc_func();
+                class
+            }),
             PostProcessorWrapper::Template(_) => *memoize!(RClass: {
                 let class: RClass = crate::processors().const_get("TemplateProcessing").unwrap();
                 class.undef_alloc_func();
                 class
             }),
-            _ => todo!(),
         }
     }
 }
@@ -203,6 +221,9 @@ pub fn processors(module: &RModule) -> RbResult<()> {
     let class = module.define_class("RobertaProcessing", post_processor)?;
     class.define_singleton_method("_new", function!(RbRobertaProcessing::new, 4))?;
 
+    let class = module.define_class("Sequence", post_processor)?;
+    class.define_singleton_method("new", function!(RbSequence::new, 1))?;
+
     let class = module.define_class("TemplateProcessing", post_processor)?;
     class.define_singleton_method("_new", function!(RbTemplateProcessing::new, 3))?;
 
@@ -1,13 +1,37 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ffi::c_void;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::panic;
 use std::path::PathBuf;
-
-use magnus::{exception, Error, RArray, RHash, Symbol, TryConvert, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use magnus::block::Proc;
+use magnus::rb_sys::AsRawValue;
+use magnus::typed_data::DataTypeBuilder;
+use magnus::{
+    exception, memoize, DataType, DataTypeFunctions, Error, Module, RArray, RClass, RHash, Symbol,
+    TryConvert, TypedData, Value,
+};
+use rb_sys::{rb_data_type_t, rb_data_typed_object_wrap, rb_define_alloc_func, VALUE};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use tk::tokenizer::{
-    Model, PaddingDirection, PaddingParams, PaddingStrategy,
+    pad_encodings, Model, PaddingDirection, PaddingParams, PaddingStrategy,
     TruncationDirection, TruncationParams, TruncationStrategy, TokenizerImpl
 };
-use tk::AddedToken;
+use tk::decoders::wordpiece::cleanup;
+use tk::models::bpe::BPE;
+use tk::utils::parallelism::MaybeParallelIterator;
+use tk::{
+    AddedToken, AddedVocabulary, Decoder, NormalizedString, Normalizer, OffsetType, PreTokenizer,
+    Trainer,
+};
 
 use crate::tk::PostProcessor;
 
@@ -18,8 +42,11 @@ use super::normalizers::RbNormalizer;
 use super::pre_tokenizers::RbPreTokenizer;
 use super::processors::RbPostProcessor;
 use super::trainers::RbTrainer;
+use super::utils::{check_interrupts, map_without_gvl, utf8_string, without_gvl};
 use super::{RbError, RbResult};
 
+#[derive(Clone)]
+#[magnus::wrap(class = "Tokenizers::AddedToken")]
 pub struct RbAddedToken {
     pub content: String,
     pub is_special_token: bool,
@@ -41,6 +68,48 @@ impl RbAddedToken {
         }
     }
 
+    pub fn new(
+        content: String,
+        special: bool,
+        single_word: bool,
+        lstrip: bool,
+        rstrip: bool,
+        normalized: Option<bool>,
+    ) -> Self {
+        Self {
+            content,
+            is_special_token: special,
+            single_word: Some(single_word),
+            lstrip: Some(lstrip),
+            rstrip: Some(rstrip),
+            normalized,
+        }
+    }
+
+    pub fn content(&self) -> String {
+        self.content.clone()
+    }
+
+    pub fn special(&self) -> bool {
+        self.is_special_token
+    }
+
+    pub fn single_word(&self) -> bool {
+        self.get_token().single_word
+    }
+
+    pub fn lstrip(&self) -> bool {
+        self.get_token().lstrip
+    }
+
+    pub fn rstrip(&self) -> bool {
+        self.get_token().rstrip
+    }
+
+    pub fn normalized(&self) -> bool {
+        self.get_token().normalized
+    }
+
     pub fn get_token(&self) -> tk::tokenizer::AddedToken {
         let mut token = tk::AddedToken::from(&self.content, self.is_special_token);
 
@@ -69,16 +138,43 @@ impl From<tk::AddedToken> for RbAddedToken {
             lstrip: Some(token.lstrip),
             rstrip: Some(token.rstrip),
             normalized: Some(token.normalized),
-            is_special_token: !token.normalized,
+            is_special_token: token.special,
         }
     }
 }
 
+// accepts either a String or an AddedToken
+fn added_tokens(tokens: RArray, is_special_token: bool) -> RbResult<Vec<AddedToken>> {
+    tokens
+        .each()
+        .map(|token| {
+            let token = token?;
+            if let Ok(content) = token.try_convert::<String>() {
+                Ok(RbAddedToken::from(content, Some(is_special_token)).get_token())
+            } else {
+                let mut token = token.try_convert::<&RbAddedToken>()?.clone();
+                if is_special_token {
+                    token.is_special_token = true;
+                }
+                Ok(token.get_token())
+            }
+        })
+        .collect()
+}
+
+// matches the entries of "added_tokens" in tokenizer.json
+#[derive(Serialize, Deserialize)]
+struct AddedTokenWithId {
+    id: u32,
+    #[serde(flatten)]
+    token: AddedToken,
+}
+
 struct TextInputSequence<'s>(tk::InputSequence<'s>);
 
 impl<'s> TryConvert for TextInputSequence<'s> {
     fn try_convert(ob: Value) -> RbResult<Self> {
-        Ok(Self(ob.try_convert::<String>()?.into()))
+        Ok(Self(utf8_string(ob)?.into()))
     }
 }
 
@@ -124,24 +220,19 @@ struct TextEncodeInput<'s>(tk::EncodeInput<'s>);
 
 impl<'s> TryConvert for TextEncodeInput<'s> {
     fn try_convert(ob: Value) -> RbResult<Self> {
-        if let Ok(i) = ob.try_convert::<TextInputSequence>() {
-            return Ok(Self(i.into()));
-        }
-        if let Ok((i1, i2)) = ob.try_convert::<(TextInputSequence, TextInputSequence)>() {
-            return Ok(Self((i1, i2).into()));
-        }
-        // TODO check if this branch is needed
-        if let Ok(arr) = ob.try_convert::<RArray>() {
+        // check for a pair first so conversion errors for strings are not hidden
+        if let Some(arr) = RArray::from_value(ob) {
             if arr.len() == 2 {
-                let first = arr.entry::<TextInputSequence>(0).unwrap();
-                let second = arr.entry::<TextInputSequence>(1).unwrap();
+                let first = arr.entry::<TextInputSequence>(0)?;
+                let second = arr.entry::<TextInputSequence>(1)?;
                 return Ok(Self((first, second).into()));
             }
+            return Err(Error::new(
+                exception::type_error(),
+                "TextEncodeInput must be a string or pair of strings",
+            ));
         }
-        Err(Error::new(
-            exception::type_error(),
-            "TextEncodeInput must be a string or pair of strings",
-        ))
+        Ok(Self(ob.try_convert::<TextInputSequence>()?.into()))
     }
 }
 
@@ -184,17 +275,212 @@ impl<'s> From<PreTokenizedEncodeInput<'s>> for tk::tokenizer::EncodeInput<'s> {
     }
 }
 
+// number of sequences buffered between the Ruby thread and the trainer
+const TRAIN_CHANNEL_SIZE: usize = 1024;
+
+// how often the Ruby thread checks for interrupts while waiting for progress
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// sequences sent to the thread running the trainer
+struct RbSequenceIter {
+    receiver: mpsc::IntoIter<String>,
+    length: Option<usize>,
+}
+
+impl Iterator for RbSequenceIter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.receiver.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.length)
+    }
+}
+
+// stops training before the model is updated if feeding it was cancelled, and
+// keeps the special tokens so they can be added to the original tokenizer
+struct RbBackgroundTrainer<'a> {
+    trainer: RbTrainer,
+    show_progress: bool,
+    cancelled: &'a AtomicBool,
+    special_tokens: Mutex<Vec<AddedToken>>,
+}
+
+impl Trainer for RbBackgroundTrainer<'_> {
+    type Model = RbModel;
+
+    fn should_show_progress(&self) -> bool {
+        self.show_progress
+    }
+
+    fn train(&self, model: &mut RbModel) -> tk::Result<Vec<AddedToken>> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err("Training was cancelled".into());
+        }
+        let special_tokens = self.trainer.train(model)?;
+        *self.special_tokens.lock().unwrap() = special_tokens.clone();
+        Ok(special_tokens)
+    }
+
+    fn feed<I, S, F>(&mut self, iterator: I, process: F) -> tk::Result<()>
+    where
+        I: Iterator<Item = S> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> tk::Result<Vec<String>> + Sync,
+    {
+        self.trainer.feed(iterator, process)
+    }
+}
+
+// serde only reports a line and column, so find the component that failed
+fn deserialize_error(json: &str, e: serde_json::Error) -> Error {
+    let message = serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|tokenizer| {
+            let get = |key: &str| tokenizer.get(key).filter(|v| !v.is_null());
+            get("normalizer")
+                .and_then(|v| component_error::<RbNormalizer>(v, "normalizer".into()))
+                .or_else(|| {
+                    get("pre_tokenizer")
+                        .and_then(|v| component_error::<RbPreTokenizer>(v, "pre_tokenizer".into()))
+                })
+                .or_else(|| get("model").and_then(|v| component_error::<RbModel>(v, "model".into())))
+                .or_else(|| {
+                    get("post_processor")
+                        .and_then(|v| component_error::<RbPostProcessor>(v, "post_processor".into()))
+                })
+                .or_else(|| get("decoder").and_then(|v| component_error::<RbDecoder>(v, "decoder".into())))
+        })
+        .unwrap_or_else(|| e.to_string());
+    RbError::from(message.into())
+}
+
+fn component_error<T: DeserializeOwned>(value: &serde_json::Value, path: String) -> Option<String> {
+    let e = serde_json::from_value::<T>(value.clone()).err()?;
+    let path = match value.get("type").and_then(|t| t.as_str()) {
+        Some(t) => format!("{} ({})", path, t),
+        None => path,
+    };
+    for key in ["normalizers", "pretokenizers", "processors", "decoders"] {
+        if let Some(elements) = value.get(key).and_then(|v| v.as_array()) {
+            for (i, element) in elements.iter().enumerate() {
+                let element_path = format!("{}.{}[{}]", path, key, i);
+                if let Some(message) = component_error::<T>(element, element_path) {
+                    return Some(message);
+                }
+            }
+        }
+    }
+    Some(format!("{}: {}", path, e))
+}
+
 type Tokenizer = TokenizerImpl<RbModel, RbNormalizer, RbPreTokenizer, RbPostProcessor, RbDecoder>;
 
-#[magnus::wrap(class = "Tokenizers::Tokenizer")]
+// decodes `ids` along with the char span each id contributes to the text
+//
+// spans come from the decoder's per-token pieces, so they always index into the
+// returned text, even when the decoder rewrites a piece (e.g. WordPiece cleanup).
+// Decoders that merge tokens into fewer pieces (ByteLevel, Fuse, ByteFallback, CTC)
+// are run on each prefix of the tokens instead, so a char split across tokens
+// belongs to the token that completes it
+fn decode_with_offsets(
+    tokenizer: &Tokenizer,
+    skipped_ids: &HashSet<u32>,
+    ids: Vec<u32>,
+) -> tk::Result<(String, Vec<(usize, usize)>)> {
+    // same filtering as TokenizerImpl::decode, keeping a slot for every id
+    let tokens: Vec<Option<String>> = ids
+        .into_iter()
+        .map(|id| {
+            tokenizer
+                .id_to_token(id)
+                .filter(|_| !skipped_ids.contains(&id))
+        })
+        .collect();
+    let present: Vec<String> = tokens.iter().flatten().cloned().collect();
+
+    let decode_chain = |tokens: Vec<String>| match tokenizer.get_decoder() {
+        Some(decoder) => decoder.decode_chain(tokens),
+        None => Ok(tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| if i == 0 { t } else { format!(" {}", t) })
+            .collect()),
+    };
+
+    let pieces = decode_chain(present.clone())?;
+    let text = pieces.concat();
+    let ends: Vec<usize> = if pieces.len() == present.len() {
+        pieces
+            .iter()
+            .scan(0, |end, piece| {
+                *end += piece.chars().count();
+                Some(*end)
+            })
+            .collect()
+    } else {
+        (1..=present.len())
+            .map(|n| {
+                let prefix = decode_chain(present[..n].to_vec())?.concat();
+                Ok(prefix.chars().zip(text.chars()).take_while(|(a, b)| a == b).count())
+            })
+            .collect::<tk::Result<_>>()?
+    };
+
+    let mut ends = ends.into_iter();
+    let mut end = 0;
+    let offsets = tokens
+        .iter()
+        .map(|token| {
+            let start = end;
+            if token.is_some() {
+                end = ends.next().map_or(start, |e| e.max(start));
+            }
+            (start, end)
+        })
+        .collect();
+    Ok((text, offsets))
+}
+
+#[derive(DataTypeFunctions)]
 pub struct RbTokenizer {
     tokenizer: RefCell<Tokenizer>,
+    // rebuilt for encode_profile, and reset by the methods that change the added tokens,
+    // model, or normalizer
+    added_vocabulary: RefCell<Option<AddedVocabulary>>,
+}
+
+// unlike #[magnus::wrap], this keeps the allocator defined below, so the
+// built-in dup and clone can copy frozen state and singleton methods
+unsafe impl TypedData for RbTokenizer {
+    fn class() -> RClass {
+        *memoize!(RClass: crate::module().const_get("Tokenizer").unwrap())
+    }
+
+    fn data_type() -> &'static DataType {
+        memoize!(DataType: DataTypeBuilder::<RbTokenizer>::new("Tokenizers::Tokenizer").build())
+    }
+}
+
+// allocates a tokenizer with an empty model for initialize_copy to fill in
+unsafe extern "C" fn allocate(class: VALUE) -> VALUE {
+    let data = Box::new(RbTokenizer::new(TokenizerImpl::new(BPE::default().into())));
+    // DataType is a transparent wrapper around rb_data_type_t
+    let data_type = RbTokenizer::data_type() as *const DataType as *const rb_data_type_t;
+    rb_data_typed_object_wrap(class, Box::into_raw(data) as *mut c_void, data_type)
+}
+
+pub fn define_allocator(class: RClass) {
+    unsafe { rb_define_alloc_func(class.as_raw(), Some(allocate)) }
 }
 
 impl RbTokenizer {
     pub fn new(tokenizer: Tokenizer) -> Self {
         Self {
             tokenizer: RefCell::new(tokenizer),
+            added_vocabulary: RefCell::new(None),
         }
     }
 
@@ -202,45 +488,294 @@ impl RbTokenizer {
         RbTokenizer::new(TokenizerImpl::new(model.clone()))
     }
 
+    pub fn from_str(json: String) -> RbResult<Self> {
+        serde_json::from_str(&json)
+            .map(RbTokenizer::new)
+            .map_err(|e| deserialize_error(&json, e))
+    }
+
     pub fn from_file(path: PathBuf) -> RbResult<Self> {
-        Tokenizer::from_file(path)
-            .map(|v| RbTokenizer {
-                tokenizer: RefCell::new(v),
-            })
-            .map_err(RbError::from)
+        let json = fs::read_to_string(path).map_err(|e| RbError::from(e.into()))?;
+        Self::from_str(json)
     }
 
     pub fn to_str(&self, pretty: bool) -> RbResult<String> {
         self.tokenizer.borrow().to_string(pretty).map_err(RbError::from)
     }
 
-    pub fn add_special_tokens(&self, tokens: Vec<String>) -> usize {
-        let tokens: Vec<AddedToken> = tokens.iter().map(|t| AddedToken::from(t, true)).collect();
-        self.tokenizer.borrow_mut().add_special_tokens(&tokens)
+    // cloning the inner tokenizer would share its components through their
+    // Arc, so copy through the serialized form to get an independent tokenizer
+    pub fn initialize_copy(&self, other: &RbTokenizer) -> RbResult<()> {
+        let copy = Self::from_str(other.to_str(false)?)?;
+        self.tokenizer.replace(copy.tokenizer.into_inner());
+        self.added_vocabulary.take();
+        Ok(())
     }
 
-    pub fn train(&self, files: Vec<String>, trainer: Option<&RbTrainer>) -> RbResult<()> {
+    pub fn add_special_tokens(&self, tokens: RArray) -> RbResult<usize> {
+        let tokens = added_tokens(tokens, true)?;
+        self.added_vocabulary.take();
+        Ok(self.tokenizer.borrow_mut().add_special_tokens(&tokens))
+    }
+
+    pub fn train(
+        &self,
+        files: Vec<String>,
+        trainer: Option<&RbTrainer>,
+        progress: Option<Proc>,
+    ) -> RbResult<()> {
         let mut trainer = trainer.map_or_else(
             || self.tokenizer.borrow().get_model().get_trainer(),
             |t| t.clone(),
         );
-        self.tokenizer
-            .borrow_mut()
-            .train_from_files(&mut trainer, files)
-            .map(|_| {})
-            .map_err(RbError::from)
+        let progress = match progress {
+            Some(progress) => progress,
+            None => {
+                self.added_vocabulary.take();
+                return self
+                    .tokenizer
+                    .borrow_mut()
+                    .train_from_files(&mut trainer, files)
+                    .map(|_| {})
+                    .map_err(RbError::from)
+            }
+        };
+
+        // opened up front so missing files are reported before training starts
+        let readers = files
+            .iter()
+            .map(|f| {
+                File::open(f)
+                    .map(|f| BufReader::with_capacity(1_000_000, f))
+                    .map_err(|e| RbError::from(e.into()))
+            })
+            .collect::<RbResult<Vec<_>>>()?;
+        let total = files
+            .iter()
+            .filter_map(|f| fs::metadata(f).ok())
+            .map(|m| m.len())
+            .sum::<u64>();
+
+        // the files are read on another thread, so this thread only holds the GVL
+        // to call the block, which happens when the percentage read changes
+        let feed = |sender: &SyncSender<String>| -> RbResult<()> {
+            let sender = sender.clone();
+            let stopped = AtomicBool::new(false);
+            let (progress_sender, progress_receiver) = mpsc::channel();
+
+            thread::scope(|s| {
+                let stopped = &stopped;
+                // owns progress_sender, so the loop below ends once the files are read
+                let reading = s.spawn(move || -> std::io::Result<()> {
+                    let mut read = 0;
+                    let mut percent = 0;
+                    for mut reader in readers {
+                        loop {
+                            // keep line endings like train_from_files
+                            let mut line = String::new();
+                            if reader.read_line(&mut line)? == 0 {
+                                break;
+                            }
+                            read += line.len() as u64;
+                            let current = read * 100 / total.max(1);
+                            if current > percent {
+                                percent = current;
+                                let _ = progress_sender.send(read.min(total));
+                            }
+                            // the trainer stopped early and its error is returned later
+                            if stopped.load(Ordering::Relaxed) || sender.send(line).is_err() {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Ok(())
+                });
+
+                let report_progress = || -> RbResult<()> {
+                    loop {
+                        let interrupted = AtomicBool::new(false);
+                        let received = without_gvl(
+                            || loop {
+                                match progress_receiver.recv_timeout(PROGRESS_POLL_INTERVAL) {
+                                    Err(RecvTimeoutError::Timeout) if !interrupted.load(Ordering::Relaxed) => {}
+                                    received => return received,
+                                }
+                            },
+                            &interrupted,
+                        );
+                        match received {
+                            Some(Ok(read)) => {
+                                progress.call::<_, Value>((read, total, "Pre-processing files"))?;
+                            }
+                            // the reader is done
+                            Some(Err(RecvTimeoutError::Disconnected)) => return Ok(()),
+                            _ => check_interrupts()?,
+                        }
+                    }
+                };
+                let reported = report_progress();
+                if reported.is_err() {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+                let read = reading.join().unwrap_or_else(|e| panic::resume_unwind(e));
+                reported?;
+                read.map_err(|e| RbError::from(e.into()))
+            })
+        };
+        let report = |current: u64, total: u64, description: &str| {
+            progress
+                .call::<_, Value>((current, total, description))
+                .map(|_| ())
+        };
+        self.train_in_background(trainer, false, None, feed, report)
     }
 
-    pub fn save(&self, path: String, pretty: bool) -> RbResult<()> {
-        self.tokenizer
-            .borrow()
-            .save(&path, pretty)
-            .map_err(RbError::from)
+    pub fn train_from_iterator(
+        &self,
+        enumerator: Value,
+        trainer: Option<&RbTrainer>,
+        length: Option<usize>,
+    ) -> RbResult<()> {
+        let trainer = trainer.map_or_else(
+            || self.tokenizer.borrow().get_model().get_trainer(),
+            |t| t.clone(),
+        );
+        let show_progress = trainer.should_show_progress();
+        let feed = |sender: &SyncSender<String>| -> RbResult<()> {
+            loop {
+                let sequence = match enumerator.funcall::<_, _, Value>("next", ()) {
+                    Ok(sequence) => sequence.try_convert::<String>()?,
+                    Err(e) if e.is_kind_of(exception::stop_iteration()) => return Ok(()),
+                    Err(e) => return Err(e),
+                };
+                // the trainer stopped early and its error is returned later
+                if sender.send(sequence).is_err() {
+                    return Ok(());
+                }
+            }
+        };
+        self.train_in_background(trainer, show_progress, length, feed, |_, _, _| Ok(()))
     }
 
-    pub fn add_tokens(&self, tokens: Vec<String>) -> usize {
-        let tokens: Vec<AddedToken> = tokens.iter().map(|t| AddedToken::from(t, true)).collect();
-        self.tokenizer.borrow_mut().add_tokens(&tokens)
+    // trains a copy of the tokenizer in another thread while `feed` sends it
+    // sequences from this thread, so `feed` and `report` can run Ruby code (including
+    // methods on this tokenizer) without it ever being called from another thread
+    //
+    // the copy shares the model, so it's trained in place
+    fn train_in_background<F, R>(
+        &self,
+        trainer: RbTrainer,
+        show_progress: bool,
+        length: Option<usize>,
+        feed: F,
+        mut report: R,
+    ) -> RbResult<()>
+    where
+        F: FnOnce(&SyncSender<String>) -> RbResult<()>,
+        R: FnMut(u64, u64, &str) -> RbResult<()>,
+    {
+        let mut tokenizer = self.tokenizer.borrow().clone();
+        let cancelled = AtomicBool::new(false);
+        let mut trainer = RbBackgroundTrainer {
+            trainer,
+            show_progress,
+            cancelled: &cancelled,
+            special_tokens: Mutex::new(Vec::new()),
+        };
+        let (sender, receiver) = mpsc::sync_channel(TRAIN_CHANNEL_SIZE);
+        let iterator = RbSequenceIter {
+            receiver: receiver.into_iter(),
+            length,
+        };
+
+        let (fed, trained) = thread::scope(|s| {
+            let worker = s.spawn(|| tokenizer.train(&mut trainer, iterator).map(|_| {}));
+
+            // the trainers don't expose their progress, so training is only reported when it
+            // starts and finishes. This is sent before the sender is dropped, so training
+            // can't have started yet
+            let fed = feed(&sender).and_then(|_| report(0, 1, "Training"));
+            if fed.is_err() {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            drop(sender);
+
+            // interrupts also cancel training if it hasn't started yet
+            let mut worker = Some(worker);
+            let trained = match without_gvl(|| worker.take().unwrap().join(), &cancelled) {
+                Some(trained) => trained,
+                None => worker.take().unwrap().join(),
+            };
+            (fed, trained)
+        });
+
+        let trained = trained.unwrap_or_else(|e| panic::resume_unwind(e));
+        fed?;
+        check_interrupts()?;
+        trained.map_err(RbError::from)?;
+
+        let special_tokens = trainer.special_tokens.into_inner().unwrap();
+        self.added_vocabulary.take();
+        self.tokenizer.borrow_mut().add_special_tokens(&special_tokens);
+        report(1, 1, "Training")
+    }
+
+    pub fn save(&self, path: PathBuf, pretty: bool) -> RbResult<()> {
+        let save_error = |e: std::io::Error| {
+            RbError::from(format!("Unable to save tokenizer to {}: {}", path.display(), e).into())
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(save_error)?;
+        }
+        let json = self.to_str(pretty)?;
+        fs::write(&path, json).map_err(save_error)
+    }
+
+    pub fn add_tokens(&self, tokens: RArray) -> RbResult<usize> {
+        let tokens = added_tokens(tokens, false)?;
+        self.added_vocabulary.take();
+        Ok(self.tokenizer.borrow_mut().add_tokens(&tokens))
+    }
+
+    // the added vocabulary is only reachable through its serialized form
+    fn added_tokens_with_id(&self) -> RbResult<Vec<AddedTokenWithId>> {
+        let mut value = serde_json::to_value(&*self.tokenizer.borrow()).map_err(|e| RbError::from(e.into()))?;
+        serde_json::from_value(value["added_tokens"].take()).map_err(|e| RbError::from(e.into()))
+    }
+
+    pub fn added_tokens_decoder(&self) -> RbResult<HashMap<u32, RbAddedToken>> {
+        Ok(self
+            .added_tokens_with_id()?
+            .into_iter()
+            .map(|t| (t.id, t.token.into()))
+            .collect())
+    }
+
+    pub fn export_added_tokens(&self, path: PathBuf) -> RbResult<()> {
+        let added_tokens = self.added_tokens_with_id()?;
+        let file = File::create(path).map_err(|e| RbError::from(e.into()))?;
+        serde_json::to_writer_pretty(file, &added_tokens).map_err(|e| RbError::from(e.into()))
+    }
+
+    pub fn import_added_tokens(&self, path: PathBuf) -> RbResult<usize> {
+        let file = File::open(path).map_err(|e| RbError::from(e.into()))?;
+        let mut added_tokens: Vec<AddedTokenWithId> =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| RbError::from(e.into()))?;
+        added_tokens.sort_by_key(|t| t.id);
+
+        // add one at a time to keep the original order
+        self.added_vocabulary.take();
+        let mut tokenizer = self.tokenizer.borrow_mut();
+        Ok(added_tokens
+            .into_iter()
+            .map(|t| {
+                if t.token.special {
+                    tokenizer.add_special_tokens(&[t.token])
+                } else {
+                    tokenizer.add_tokens(&[t.token])
+                }
+            })
+            .sum())
     }
 
     pub fn encode(
@@ -270,10 +805,92 @@ impl RbTokenizer {
         self.tokenizer
             .borrow()
             .encode_char_offsets(input, add_special_tokens)
-            .map(|v| RbEncoding { encoding: v })
+            .map(RbEncoding::from)
             .map_err(RbError::from)
     }
 
+    pub fn normalize(&self, sequence: Value) -> RbResult<String> {
+        let mut normalized = NormalizedString::from(utf8_string(sequence)?);
+        if let Some(normalizer) = self.tokenizer.borrow().get_normalizer() {
+            normalizer.normalize(&mut normalized).map_err(RbError::from)?;
+        }
+        Ok(normalized.get().to_owned())
+    }
+
+    // runs each stage of the encode pipeline separately to time it, and returns the
+    // encoding those stages produced. Added tokens are matched in the same pass as
+    // normalization upstream, so they're included in the normalizer time
+    // runs the stages of encode one at a time to time them, following
+    // TokenizerImpl::encode_single_sequence and post_process, so the result matches
+    // encode for text input (which the tests check)
+    pub fn encode_profile(
+        &self,
+        sequence: Value,
+        pair: Option<Value>,
+        add_special_tokens: bool,
+    ) -> RbResult<(RbEncoding, HashMap<String, u64>)> {
+        let sequence = utf8_string(sequence)?;
+        let pair = pair.map(utf8_string).transpose()?;
+        fn record(stats: &mut HashMap<String, u64>, stage: &str, start: Instant) {
+            *stats.entry(stage.to_string()).or_default() += start.elapsed().as_nanos() as u64;
+        }
+
+        // the added vocabulary isn't reachable from the tokenizer, so rebuild it
+        // (in id order, so ids match) before timing anything
+        let tokenizer = self.tokenizer.borrow();
+        if self.added_vocabulary.borrow().is_none() {
+            let mut added_tokens = self.added_tokens_with_id()?;
+            added_tokens.sort_by_key(|t| t.id);
+            let mut added_vocabulary = AddedVocabulary::new();
+            for t in added_tokens {
+                added_vocabulary.add_tokens(&[t.token], tokenizer.get_model(), tokenizer.get_normalizer());
+            }
+            self.added_vocabulary.replace(Some(added_vocabulary));
+        }
+        let added_vocabulary = self.added_vocabulary.borrow();
+        let added_vocabulary = added_vocabulary.as_ref().unwrap();
+
+        let total = Instant::now();
+        let mut stats = HashMap::new();
+        let mut encodings = Vec::with_capacity(2);
+
+        for (type_id, seq) in std::iter::once(&sequence).chain(pair.as_ref()).enumerate() {
+            let start = Instant::now();
+            let mut pre_tokenized = added_vocabulary.extract_and_normalize(tokenizer.get_normalizer(), seq);
+            record(&mut stats, "normalizer", start);
+
+            let start = Instant::now();
+            if let Some(pre_tokenizer) = tokenizer.get_pre_tokenizer() {
+                pre_tokenizer.pre_tokenize(&mut pre_tokenized).map_err(RbError::from)?;
+            }
+            record(&mut stats, "pre_tokenizer", start);
+
+            let start = Instant::now();
+            let model = tokenizer.get_model();
+            pre_tokenized
+                .tokenize(|normalized| model.tokenize(normalized.get()))
+                .map_err(RbError::from)?;
+            let encoding = pre_tokenized
+                .into_encoding(None, type_id as u32, OffsetType::Char)
+                .map_err(RbError::from)?;
+            record(&mut stats, "model", start);
+
+            encodings.push(encoding);
+        }
+
+        // also applies truncation and padding, like encode
+        let start = Instant::now();
+        let mut encodings = encodings.into_iter();
+        let encoding = encodings.next().unwrap();
+        let encoding = tokenizer
+            .post_process(encoding, encodings.next(), add_special_tokens)
+            .map_err(RbError::from)?;
+        record(&mut stats, "post_processor", start);
+        record(&mut stats, "total", total);
+
+        Ok((encoding.into(), stats))
+    }
+
     pub fn encode_batch(
         &self,
         input: RArray,
@@ -291,49 +908,122 @@ impl RbTokenizer {
                 Ok(input)
             })
             .collect::<RbResult<Vec<tk::EncodeInput>>>()?;
-        self.tokenizer
-            .borrow()
-            .encode_batch_char_offsets(input, add_special_tokens)
-            .map(|encodings| {
-                encodings
-                    .into_iter()
-                    .map(Into::<RbEncoding>::into)
-                    .collect()
-            })
-            .map_err(RbError::from)
+
+        // work on a copy so other threads can modify the tokenizer while the GVL is
+        // released, and pad once at the end since the batch is encoded in chunks
+        let mut tokenizer = self.tokenizer.borrow().clone();
+        let padding = tokenizer.get_padding().cloned();
+        tokenizer.with_padding(None);
+        let mut encodings = map_without_gvl(input, |input| {
+            tokenizer.encode_batch_char_offsets(input, add_special_tokens)
+        })?;
+        if let Some(params) = padding {
+            pad_encodings(&mut encodings, &params).map_err(RbError::from)?;
+        }
+        Ok(encodings
+            .into_iter()
+            .map(Into::<RbEncoding>::into)
+            .collect())
     }
 
-    pub fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> RbResult<String> {
+    pub fn decode(
+        &self,
+        ids: Vec<u32>,
+        skip_special_tokens: bool,
+        clean_up_tokenization_spaces: bool,
+    ) -> RbResult<String> {
         self.tokenizer
             .borrow()
             .decode(ids, skip_special_tokens)
+            .map(|s| if clean_up_tokenization_spaces { cleanup(&s) } else { s })
             .map_err(RbError::from)
     }
 
-    pub fn decode_batch(&self, sequences: Vec<Vec<u32>>, skip_special_tokens: bool) -> RbResult<Vec<String>> {
-        self.tokenizer
-            .borrow()
-            .decode_batch(sequences, skip_special_tokens)
-            .map_err(RbError::from)
+    pub fn decode_batch(
+        &self,
+        sequences: Vec<Vec<u32>>,
+        skip_special_tokens: bool,
+        clean_up_tokenization_spaces: bool,
+    ) -> RbResult<Vec<String>> {
+        // work on a copy so other threads can modify the tokenizer while the GVL is released
+        let tokenizer = self.tokenizer.borrow().clone();
+        let decoded = map_without_gvl(sequences, |sequences| {
+            tokenizer.decode_batch(sequences, skip_special_tokens)
+        })?;
+        Ok(if clean_up_tokenization_spaces {
+            decoded.iter().map(|s| cleanup(s)).collect()
+        } else {
+            decoded
+        })
+    }
+
+    pub fn decode_batch_with_offsets(
+        &self,
+        sequences: Vec<Vec<u32>>,
+        skip_special_tokens: bool,
+    ) -> RbResult<Vec<(String, Vec<(usize, usize)>)>> {
+        let skipped_ids: HashSet<u32> = if skip_special_tokens {
+            self.added_tokens_with_id()?
+                .into_iter()
+                .filter(|t| t.token.special)
+                .map(|t| t.id)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        // work on a copy so other threads can modify the tokenizer while the GVL is released
+        let tokenizer = self.tokenizer.borrow().clone();
+        map_without_gvl(sequences, |sequences| {
+            sequences
+                .into_maybe_par_iter()
+                .map(|ids| decode_with_offsets(&tokenizer, &skipped_ids, ids))
+                .collect()
+        })
+    }
+
+    pub fn model(&self) -> RbModel {
+        self.tokenizer.borrow().get_model().clone()
+    }
+
+    pub fn set_model(&self, model: &RbModel) {
+        self.added_vocabulary.take();
+        self.tokenizer.borrow_mut().with_model(model.clone());
+    }
+
+    pub fn decoder(&self) -> Option<RbDecoder> {
+        self.tokenizer.borrow().get_decoder().cloned()
     }
 
     pub fn set_decoder(&self, decoder: &RbDecoder) {
         self.tokenizer.borrow_mut().with_decoder(decoder.clone());
     }
 
+    pub fn pre_tokenizer(&self) -> Option<RbPreTokenizer> {
+        self.tokenizer.borrow().get_pre_tokenizer().cloned()
+    }
+
     pub fn set_pre_tokenizer(&self, pretok: &RbPreTokenizer) {
         self.tokenizer
             .borrow_mut()
             .with_pre_tokenizer(pretok.clone());
     }
 
+    pub fn post_processor(&self) -> Option<RbPostProcessor> {
+        self.tokenizer.borrow().get_post_processor().cloned()
+    }
+
     pub fn set_post_processor(&self, processor: &RbPostProcessor) {
         self.tokenizer
             .borrow_mut()
             .with_post_processor(processor.clone());
     }
 
+    pub fn normalizer(&self) -> Option<RbNormalizer> {
+        self.tokenizer.borrow().get_normalizer().cloned()
+    }
+
     pub fn set_normalizer(&self, normalizer: &RbNormalizer) {
+        self.added_vocabulary.take();
         self.tokenizer
             .borrow_mut()
             .with_normalizer(normalizer.clone());
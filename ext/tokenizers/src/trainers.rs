@@ -227,6 +227,38 @@ impl RbTrainer {
         );
     }
 
+    fn unigram_trainer_shrinking_factor(&self) -> f64 {
+        getter!(self, UnigramTrainer, shrinking_factor)
+    }
+
+    fn unigram_trainer_set_shrinking_factor(&self, shrinking_factor: f64) {
+        setter!(self, UnigramTrainer, shrinking_factor, shrinking_factor);
+    }
+
+    fn unigram_trainer_unk_token(&self) -> Option<String> {
+        getter!(self, UnigramTrainer, unk_token.clone())
+    }
+
+    fn unigram_trainer_set_unk_token(&self, unk_token: Option<String>) {
+        setter!(self, UnigramTrainer, unk_token, unk_token);
+    }
+
+    fn unigram_trainer_max_piece_length(&self) -> usize {
+        getter!(self, UnigramTrainer, max_piece_length)
+    }
+
+    fn unigram_trainer_set_max_piece_length(&self, max_piece_length: usize) {
+        setter!(self, UnigramTrainer, max_piece_length, max_piece_length);
+    }
+
+    fn unigram_trainer_n_sub_iterations(&self) -> u32 {
+        getter!(self, UnigramTrainer, n_sub_iterations)
+    }
+
+    fn unigram_trainer_set_n_sub_iterations(&self, n_sub_iterations: u32) {
+        setter!(self, UnigramTrainer, n_sub_iterations, n_sub_iterations);
+    }
+
     fn word_level_trainer_vocab_size(&self) -> usize {
         getter!(self, WordLevelTrainer, vocab_size)
     }
@@ -714,6 +746,14 @@ pub fn trainers(module: &RModule) -> RbResult<()> {
     class.define_method("special_tokens=", method!(RbTrainer::unigram_trainer_set_special_tokens, 1))?;
     class.define_method("initial_alphabet", method!(RbTrainer::unigram_trainer_initial_alphabet, 0))?;
     class.define_method("initial_alphabet=", method!(RbTrainer::unigram_trainer_set_initial_alphabet, 1))?;
+    class.define_method("shrinking_factor", method!(RbTrainer::unigram_trainer_shrinking_factor, 0))?;
+    class.define_method("shrinking_factor=", method!(RbTrainer::unigram_trainer_set_shrinking_factor, 1))?;
+    class.define_method("unk_token", method!(RbTrainer::unigram_trainer_unk_token, 0))?;
+    class.define_method("unk_token=", method!(RbTrainer::unigram_trainer_set_unk_token, 1))?;
+    class.define_method("max_piece_length", method!(RbTrainer::unigram_trainer_max_piece_length, 0))?;
+    class.define_method("max_piece_length=", method!(RbTrainer::unigram_trainer_set_max_piece_length, 1))?;
+    class.define_method("n_sub_iterations", method!(RbTrainer::unigram_trainer_n_sub_iterations, 0))?;
+    class.define_method("n_sub_iterations=", method!(RbTrainer::unigram_trainer_set_n_sub_iterations, 1))?;
 
     let class = module.define_class("WordLevelTrainer", trainer)?;
     class.define_singleton_method("_new", function!(RbWordLevelTrainer::new, 1))?;
@@ -0,0 +1,104 @@
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use magnus::rb_sys::{protect, AsRawValue};
+use magnus::QNIL;
+use rb_sys::{rb_thread_call_without_gvl2, rb_thread_check_ints};
+use tk::utils::parallelism::get_parallelism;
+
+use crate::{RbError, RbResult};
+
+// number of inputs processed between checks for Ruby interrupts
+const CHUNK_SIZE: usize = 1024;
+
+// runs `func` with the GVL released so other Ruby threads can make progress
+// `func` must not touch any Ruby objects
+//
+// returns None without running `func` if an interrupt is already pending,
+// and sets `interrupted` if one arrives (Thread#raise, Thread#kill, Timeout)
+// while it runs, so `func` can stop early
+pub fn without_gvl<F, R>(func: F, interrupted: &AtomicBool) -> Option<R>
+where
+    F: FnOnce() -> R,
+{
+    unsafe extern "C" fn call<F, R>(data: *mut c_void) -> *mut c_void
+    where
+        F: FnOnce() -> R,
+    {
+        let data = &mut *(data as *mut (Option<F>, Option<std::thread::Result<R>>));
+        let func = data.0.take().unwrap();
+        // panics can't unwind through the Ruby VM, so resume them once the GVL is back
+        data.1 = Some(panic::catch_unwind(AssertUnwindSafe(func)));
+        ptr::null_mut()
+    }
+
+    unsafe extern "C" fn ubf(data: *mut c_void) {
+        (*(data as *const AtomicBool)).store(true, Ordering::Relaxed);
+    }
+
+    let mut data: (Option<F>, Option<std::thread::Result<R>>) = (Some(func), None);
+    // unlike rb_thread_call_without_gvl, this doesn't raise on pending interrupts,
+    // which would unwind through the Rust frames
+    unsafe {
+        rb_thread_call_without_gvl2(
+            Some(call::<F, R>),
+            &mut data as *mut _ as *mut c_void,
+            Some(ubf),
+            interrupted as *const AtomicBool as *mut c_void,
+        );
+    }
+    data.1.map(|result| match result {
+        Ok(result) => result,
+        Err(e) => panic::resume_unwind(e),
+    })
+}
+
+// raises any pending interrupt for the current thread
+pub fn check_interrupts() -> RbResult<()> {
+    protect(|| {
+        unsafe { rb_thread_check_ints() };
+        (*QNIL).as_raw()
+    })
+    .map(|_| ())
+}
+
+// runs `func` over `items` in chunks, releasing the GVL unless parallelism is
+// disabled (in which case it runs serially while holding the GVL, which is
+// useful for debugging)
+//
+// interrupts are handled between chunks, so long batches can still be
+// killed or timed out
+pub fn map_without_gvl<T, R, F>(items: Vec<T>, func: F) -> RbResult<Vec<R>>
+where
+    F: Fn(Vec<T>) -> tk::Result<Vec<R>>,
+{
+    let release = get_parallelism();
+    let mut results = Vec::with_capacity(items.len());
+    let mut items = items.into_iter().peekable();
+
+    while items.peek().is_some() {
+        let interrupted = AtomicBool::new(false);
+        let run = || -> tk::Result<()> {
+            loop {
+                let chunk: Vec<T> = items.by_ref().take(CHUNK_SIZE).collect();
+                results.extend(func(chunk)?);
+                if items.peek().is_none() || !release || interrupted.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+            }
+        };
+        let result = if release {
+            without_gvl(run, &interrupted)
+        } else {
+            Some(run())
+        };
+        if let Some(result) = result {
+            result.map_err(RbError::from)?;
+        }
+        check_interrupts()?;
+    }
+
+    Ok(results)
+}
@@ -1,5 +1,11 @@
+mod gvl;
 mod normalization;
 mod regex;
+mod serialization;
+mod strings;
 
+pub use gvl::*;
 pub use normalization::*;
 pub use regex::*;
+pub use serialization::*;
+pub use strings::*;
@@ -1,25 +1,80 @@
 use super::regex::{regex, RbRegex};
 use crate::RbResult;
-use magnus::{exception, Error, TryConvert, Value};
+use magnus::{class, exception, Error, TryConvert, Value};
+use onig::Regex;
 use tk::normalizer::SplitDelimiterBehavior;
 use tk::pattern::Pattern;
+use tk::utils::SysRegex;
 
 #[derive(Clone)]
 pub enum RbPattern<'p> {
     Str(String),
     Regex(&'p RbRegex),
+    Regexp(String),
 }
 
 impl TryConvert for RbPattern<'_> {
     fn try_convert(obj: Value) -> RbResult<Self> {
         if obj.is_kind_of(regex()) {
             Ok(RbPattern::Regex(obj.try_convert()?))
+        } else if obj.is_kind_of(class::regexp()) {
+            Ok(RbPattern::Regexp(regexp_pattern(obj)?))
         } else {
             Ok(RbPattern::Str(obj.try_convert()?))
         }
     }
 }
 
+const IGNORECASE: u32 = 1;
+const EXTENDED: u32 = 2;
+const MULTILINE: u32 = 4;
+const FIXEDENCODING: u32 = 16;
+const NOENCODING: u32 = 32;
+
+// onig uses Ruby syntax, so options can be passed through as inline flags
+fn regexp_pattern(regexp: Value) -> RbResult<String> {
+    let source: String = regexp.funcall("source", ())?;
+    let options: u32 = regexp.funcall("options", ())?;
+
+    let unsupported = options & !(IGNORECASE | EXTENDED | MULTILINE | FIXEDENCODING);
+    if unsupported != 0 {
+        let mut names = vec![];
+        if unsupported & NOENCODING != 0 {
+            names.push("Regexp::NOENCODING".to_string());
+        }
+        // internal bits without a Regexp constant
+        let other = unsupported & !NOENCODING;
+        if other != 0 {
+            names.push(format!("{:#x}", other));
+        }
+        return Err(Error::new(
+            exception::arg_error(),
+            format!("Unsupported Regexp options: {}", names.join(", ")),
+        ));
+    }
+
+    let mut flags = String::new();
+    if options & IGNORECASE != 0 {
+        flags.push('i');
+    }
+    if options & MULTILINE != 0 {
+        flags.push('m');
+    }
+    if options & EXTENDED != 0 {
+        flags.push('x');
+    }
+
+    let pattern = if flags.is_empty() {
+        source
+    } else {
+        format!("(?{}:{})", flags, source)
+    };
+
+    // validate here so errors are raised when the pattern is passed in
+    Regex::new(&pattern).map_err(|e| Error::new(exception::regexp_error(), e.description().to_owned()))?;
+    Ok(pattern)
+}
+
 impl Pattern for RbPattern<'_> {
     fn find_matches(&self, inside: &str) -> tk::Result<Vec<(tk::Offsets, bool)>> {
         match self {
@@ -31,9 +86,8 @@ impl Pattern for RbPattern<'_> {
                     s.find_matches(inside)
                 }
             }
-            RbPattern::Regex(_r) => {
-                todo!()
-            }
+            RbPattern::Regex(r) => (&r.inner).find_matches(inside),
+            RbPattern::Regexp(r) => (&SysRegex::new(r)?).find_matches(inside),
         }
     }
 }
@@ -42,7 +96,8 @@ impl From<RbPattern<'_>> for tk::normalizers::replace::ReplacePattern {
     fn from(pattern: RbPattern<'_>) -> Self {
         match pattern {
             RbPattern::Str(s) => Self::String(s),
-            RbPattern::Regex(_r) => todo!(),
+            RbPattern::Regex(r) => Self::Regex(r.pattern.clone()),
+            RbPattern::Regexp(r) => Self::Regex(r),
         }
     }
 }
@@ -51,7 +106,8 @@ impl From<RbPattern<'_>> for tk::pre_tokenizers::split::SplitPattern {
     fn from(pattern: RbPattern<'_>) -> Self {
         match pattern {
             RbPattern::Str(s) => Self::String(s),
-            RbPattern::Regex(_r) => todo!(),
+            RbPattern::Regex(r) => Self::Regex(r.pattern.clone()),
+            RbPattern::Regexp(r) => Self::Regex(r),
         }
     }
 }
@@ -61,7 +117,11 @@ pub struct RbSplitDelimiterBehavior(pub SplitDelimiterBehavior);
 
 impl TryConvert for RbSplitDelimiterBehavior {
     fn try_convert(obj: Value) -> RbResult<Self> {
-        let s = obj.try_convert::<String>()?;
+        let s: String = if obj.is_kind_of(class::symbol()) {
+            obj.funcall("to_s", ())?
+        } else {
+            obj.try_convert()?
+        };
 
         Ok(Self(match s.as_str() {
             "removed" => Ok(SplitDelimiterBehavior::Removed),
@@ -71,13 +131,28 @@ impl TryConvert for RbSplitDelimiterBehavior {
             "contiguous" => Ok(SplitDelimiterBehavior::Contiguous),
             _ => Err(Error::new(
                 exception::arg_error(),
-                "Wrong value for SplitDelimiterBehavior, expected one of: \
-                `removed, isolated, merged_with_previous, merged_with_next, contiguous`",
+                format!(
+                    "Wrong value for SplitDelimiterBehavior: {:?}, expected one of: \
+                    removed, isolated, merged_with_previous, merged_with_next, contiguous",
+                    s
+                ),
             )),
         }?))
     }
 }
 
+impl RbSplitDelimiterBehavior {
+    pub fn as_str(&self) -> &'static str {
+        match self.0 {
+            SplitDelimiterBehavior::Removed => "removed",
+            SplitDelimiterBehavior::Isolated => "isolated",
+            SplitDelimiterBehavior::MergedWithPrevious => "merged_with_previous",
+            SplitDelimiterBehavior::MergedWithNext => "merged_with_next",
+            SplitDelimiterBehavior::Contiguous => "contiguous",
+        }
+    }
+}
+
 impl From<RbSplitDelimiterBehavior> for SplitDelimiterBehavior {
     fn from(v: RbSplitDelimiterBehavior) -> Self {
         v.0
@@ -1,20 +1,24 @@
-use onig::Regex;
 use magnus::{exception, memoize, Error, Module, RClass};
+use tk::utils::SysRegex;
 use crate::{module, RbResult};
 
 #[magnus::wrap(class = "Tokenizers::Regex")]
 pub struct RbRegex {
-    pub inner: Regex,
+    pub inner: SysRegex,
     pub pattern: String,
 }
 
 impl RbRegex {
     pub fn new(s: String) -> RbResult<Self> {
         Ok(Self {
-            inner: Regex::new(&s).map_err(|e| Error::new(exception::runtime_error(), e.description().to_owned()))?,
+            inner: SysRegex::new(&s).map_err(|e| Error::new(exception::regexp_error(), e.to_string()))?,
             pattern: s,
         })
     }
+
+    pub fn pattern(&self) -> String {
+        self.pattern.clone()
+    }
 }
 
 pub fn regex() -> RClass {
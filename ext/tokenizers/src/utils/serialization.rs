@@ -0,0 +1,54 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use magnus::Value;
+use serde::Serialize;
+
+use crate::{RbError, RbResult};
+
+// compare by the serialized form so wrapped components are compared by
+// content rather than by pointer
+pub fn serialized_eq<T: Serialize>(a: &T, b: &T) -> RbResult<bool> {
+    Ok(to_value(a)? == to_value(b)?)
+}
+
+pub fn serialized_hash<T: Serialize>(value: &T) -> RbResult<i64> {
+    let mut hasher = DefaultHasher::new();
+    to_value(value)?.to_string().hash(&mut hasher);
+    // keep it within a Fixnum
+    Ok((hasher.finish() >> 2) as i64)
+}
+
+pub fn serialized_inspect<T: Serialize>(rb_self: Value, value: &T) -> RbResult<String> {
+    // safe as the name is copied before Ruby gets a chance to free it
+    let mut s = format!("#<{}", unsafe { rb_self.classname() });
+    if let serde_json::Value::Object(fields) = to_value(value)? {
+        for (name, field) in fields.iter().filter(|(name, _)| *name != "type") {
+            s.push_str(&format!(" {}={}", name, inspect_field(field)));
+        }
+    }
+    s.push('>');
+    Ok(s)
+}
+
+fn inspect_field(field: &serde_json::Value) -> String {
+    match field {
+        serde_json::Value::Null => "nil".to_string(),
+        // only list the types of sequence children
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match item.get("type") {
+                    Some(serde_json::Value::String(t)) => t.clone(),
+                    _ => inspect_field(item),
+                })
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        _ => field.to_string(),
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> RbResult<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| RbError::from(e.into()))
+}
@@ -0,0 +1,29 @@
+use magnus::encoding::{EncodingCapable, Index, RbEncoding};
+use magnus::{RString, Symbol, TryConvert, Value};
+
+use crate::{RbError, RbResult};
+
+// accepts symbols and anything that responds to to_str
+pub fn utf8_string(value: Value) -> RbResult<String> {
+    if let Some(symbol) = Symbol::from_value(value) {
+        return Ok(symbol.name()?.into_owned());
+    }
+
+    let s = RString::try_convert(value)?;
+    // binary strings are checked as UTF-8, other encodings are converted
+    let s = if s.is_utf8_compatible_encoding() || s.enc_get() == Index::ascii8bit() {
+        s
+    } else {
+        s.conv_enc(RbEncoding::utf8())?
+    };
+
+    // safe as the bytes are copied before Ruby gets a chance to modify them
+    let bytes = unsafe { s.as_slice() }.to_vec();
+    String::from_utf8(bytes).map_err(|e| {
+        let bytes = e.as_bytes();
+        let start = e.utf8_error().valid_up_to();
+        let end = start + e.utf8_error().error_len().unwrap_or(bytes.len() - start);
+        let invalid: String = bytes[start..end].iter().map(|b| format!("\\x{:02X}", b)).collect();
+        RbError::from(format!("Invalid UTF-8 in input at byte offset {}: \"{}\"", start, invalid).into())
+    })
+}
@@ -20,15 +20,20 @@ require_relative "tokenizers/models/unigram"
 
 # normalizers
 require_relative "tokenizers/normalizers/bert_normalizer"
+require_relative "tokenizers/normalizers/normalizer"
 require_relative "tokenizers/normalizers/prepend"
+require_relative "tokenizers/normalizers/replace"
 require_relative "tokenizers/normalizers/strip"
 
 # pre-tokenizers
 require_relative "tokenizers/pre_tokenizers/byte_level"
 require_relative "tokenizers/pre_tokenizers/digits"
 require_relative "tokenizers/pre_tokenizers/metaspace"
+require_relative "tokenizers/pre_tokenizers/pre_tokenizer"
 require_relative "tokenizers/pre_tokenizers/punctuation"
 require_relative "tokenizers/pre_tokenizers/split"
+require_relative "tokenizers/pre_tokenizers/split_delimiter_behavior"
+require_relative "tokenizers/pre_tokenizers/whitespace"
 
 # processors
 require_relative "tokenizers/processors/byte_level"
@@ -42,6 +47,9 @@ require_relative "tokenizers/trainers/word_level_trainer"
 require_relative "tokenizers/trainers/word_piece_trainer"
 
 # other
+require_relative "tokenizers/added_token"
+require_relative "tokenizers/bert_word_piece_tokenizer"
+require_relative "tokenizers/byte_level_bpe_tokenizer"
 require_relative "tokenizers/char_bpe_tokenizer"
 require_relative "tokenizers/encoding"
 require_relative "tokenizers/from_pretrained"
@@ -58,4 +66,8 @@ module Tokenizers
   def self.from_file(...)
     Tokenizer.from_file(...)
   end
+
+  def self.from_str(...)
+    Tokenizer.from_str(...)
+  end
 end
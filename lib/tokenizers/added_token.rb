@@ -0,0 +1,7 @@
+module Tokenizers
+  class AddedToken
+    def self.new(content, single_word: false, lstrip: false, rstrip: false, normalized: nil, special: false)
+      _new(content, special, single_word, lstrip, rstrip, normalized)
+    end
+  end
+end
@@ -0,0 +1,80 @@
+module Tokenizers
+  class BertWordPieceTokenizer
+    def initialize(
+      vocab = nil,
+      unk_token: "[UNK]",
+      sep_token: "[SEP]",
+      cls_token: "[CLS]",
+      pad_token: "[PAD]",
+      mask_token: "[MASK]",
+      clean_text: true,
+      handle_chinese_chars: true,
+      strip_accents: nil,
+      lowercase: true,
+      wordpieces_prefix: "##"
+    )
+      @special_tokens = [unk_token, sep_token, cls_token, pad_token, mask_token]
+      @sep_token = sep_token
+      @cls_token = cls_token
+      @wordpieces_prefix = wordpieces_prefix
+
+      model_options = {unk_token: unk_token, continuing_subword_prefix: wordpieces_prefix}
+      model =
+        if vocab.is_a?(String)
+          Models::WordPiece._from_file(vocab, model_options)
+        else
+          Models::WordPiece._new(vocab, model_options)
+        end
+
+      @tokenizer = Tokenizer.new(model)
+      @tokenizer.normalizer =
+        Normalizers::BertNormalizer.new(
+          clean_text: clean_text,
+          handle_chinese_chars: handle_chinese_chars,
+          strip_accents: strip_accents,
+          lowercase: lowercase
+        )
+      @tokenizer.pre_tokenizer = PreTokenizers::BertPreTokenizer.new
+      @tokenizer.decoder = Decoders::WordPiece.new(prefix: wordpieces_prefix)
+
+      setup_special_tokens if vocab
+    end
+
+    def train(files, vocab_size: 30000, min_frequency: 2, limit_alphabet: 1000, initial_alphabet: [], show_progress: true)
+      trainer =
+        Trainers::WordPieceTrainer.new(
+          vocab_size: vocab_size,
+          min_frequency: min_frequency,
+          special_tokens: @special_tokens,
+          limit_alphabet: limit_alphabet,
+          initial_alphabet: initial_alphabet,
+          continuing_subword_prefix: @wordpieces_prefix,
+          show_progress: show_progress
+        )
+      @tokenizer.train(files, trainer)
+      setup_special_tokens
+    end
+
+    def encode(text, pair = nil, **options)
+      @tokenizer.encode(text, pair, **options)
+    end
+
+    def decode(ids, **options)
+      @tokenizer.decode(ids, **options)
+    end
+
+    private
+
+    def setup_special_tokens
+      # let the tokenizer know about special tokens that are part of the vocab
+      @tokenizer.add_special_tokens(@special_tokens.select { |t| @tokenizer.token_to_id(t) })
+
+      sep_token_id = @tokenizer.token_to_id(@sep_token)
+      raise Error, "sep_token not found in the vocabulary" unless sep_token_id
+      cls_token_id = @tokenizer.token_to_id(@cls_token)
+      raise Error, "cls_token not found in the vocabulary" unless cls_token_id
+
+      @tokenizer.post_processor = Processors::BertProcessing.new([@sep_token, sep_token_id], [@cls_token, cls_token_id])
+    end
+  end
+end
@@ -0,0 +1,55 @@
+module Tokenizers
+  class ByteLevelBPETokenizer
+    def initialize(
+      vocab = nil,
+      merges = nil,
+      add_prefix_space: false,
+      lowercase: false,
+      dropout: nil,
+      continuing_subword_prefix: nil,
+      end_of_word_suffix: nil,
+      trim_offsets: false
+    )
+      model =
+        if vocab && merges
+          Models::BPE._from_file(
+            vocab,
+            merges,
+            {
+              dropout: dropout,
+              continuing_subword_prefix: continuing_subword_prefix,
+              end_of_word_suffix: end_of_word_suffix
+            }
+          )
+        else
+          Models::BPE._new(nil, nil, {})
+        end
+
+      @tokenizer = Tokenizer.new(model)
+      @tokenizer.normalizer = Normalizers::Lowercase.new if lowercase
+      @tokenizer.pre_tokenizer = PreTokenizers::ByteLevel.new(add_prefix_space: add_prefix_space)
+      @tokenizer.decoder = Decoders::ByteLevel.new
+      @tokenizer.post_processor = Processors::ByteLevel.new(trim_offsets: trim_offsets)
+    end
+
+    def train(files, vocab_size: 30000, min_frequency: 2, special_tokens: [], show_progress: true)
+      trainer =
+        Trainers::BpeTrainer.new(
+          vocab_size: vocab_size,
+          min_frequency: min_frequency,
+          special_tokens: special_tokens,
+          initial_alphabet: PreTokenizers::ByteLevel.alphabet,
+          show_progress: show_progress
+        )
+      @tokenizer.train(files, trainer)
+    end
+
+    def encode(text, pair = nil, **options)
+      @tokenizer.encode(text, pair, **options)
+    end
+
+    def decode(ids, **options)
+      @tokenizer.decode(ids, **options)
+    end
+  end
+end
@@ -1,14 +1,36 @@
 module Tokenizers
   class CharBPETokenizer
-    def initialize(vocab, merges, unk_token: "<unk>", suffix: "</w>")
-      @tokenizer =
-        Tokenizer.new(
-          Models::BPE._from_file(vocab, merges, {unk_token: unk_token, end_of_word_suffix: suffix})
-        )
+    def initialize(vocab = nil, merges = nil, unk_token: "<unk>", suffix: "</w>", dropout: nil)
+      @unk_token = unk_token
+      @suffix = suffix
+
+      model_options = {unk_token: unk_token, end_of_word_suffix: suffix, dropout: dropout}
+      model =
+        if vocab && merges
+          Models::BPE._from_file(vocab, merges, model_options)
+        else
+          Models::BPE._new(nil, nil, model_options)
+        end
+
+      @tokenizer = Tokenizer.new(model)
       @tokenizer.add_special_tokens([unk_token])
       @tokenizer.normalizer = Normalizers::BertNormalizer.new
       @tokenizer.pre_tokenizer = PreTokenizers::BertPreTokenizer.new
-      @tokenizer.decoder = Decoders::BPEDecoder.new
+      @tokenizer.decoder = Decoders::BPEDecoder.new(suffix: suffix)
+    end
+
+    def train(files, vocab_size: 30000, min_frequency: 2, special_tokens: [@unk_token], limit_alphabet: 1000, initial_alphabet: [], show_progress: true)
+      trainer =
+        Trainers::BpeTrainer.new(
+          vocab_size: vocab_size,
+          min_frequency: min_frequency,
+          special_tokens: special_tokens,
+          limit_alphabet: limit_alphabet,
+          initial_alphabet: initial_alphabet,
+          end_of_word_suffix: @suffix,
+          show_progress: show_progress
+        )
+      @tokenizer.train(files, trainer)
     end
 
     def encode(text, **options)
@@ -1,5 +1,9 @@
 module Tokenizers
   class Encoding
+    def self.merge(encodings, growing_offsets: true)
+      _merge(encodings, growing_offsets)
+    end
+
     def word_to_tokens(word_index, sequence_index = 0)
       _word_to_tokens(word_index, sequence_index)
     end
@@ -13,7 +17,15 @@ module Tokenizers
     end
 
     def char_to_word(char_pos, sequence_index = 0)
-      _char_to_word(word_index, sequence_index)
+      _char_to_word(char_pos, sequence_index)
+    end
+
+    def truncate(max_length, stride: 0, direction: "right")
+      _truncate(max_length, stride, direction.to_s)
+    end
+
+    def pad(length, direction: "right", pad_id: 0, pad_type_id: 0, pad_token: "[PAD]")
+      _pad(length, direction.to_s, pad_id, pad_type_id, pad_token)
     end
   end
 end
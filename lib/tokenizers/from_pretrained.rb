@@ -1,7 +1,7 @@
 module Tokenizers
   module FromPretrained
     # for user agent
-    TOKENIZERS_VERSION = "0.13.3"
+    TOKENIZERS_VERSION = TK_VERSION
 
     # use Ruby for downloads
     # this avoids the need to vendor OpenSSL on Linux
@@ -30,7 +30,10 @@ module Tokenizers
       path =
         begin
           cached_path(cache_dir, url, options)
-        rescue OpenURI::HTTPError
+        rescue OpenURI::HTTPError => e
+          if auth_token && ["401", "403"].include?(e.io.status[0])
+            raise Error, "Model \"#{identifier}\" on the Hub is not accessible with the provided auth token"
+          end
           raise Error, "Model \"#{identifier}\" on the Hub doesn't have a tokenizer"
         end
 
@@ -4,6 +4,10 @@ module Tokenizers
       def self.new(vocab: nil, merges: nil, **kwargs)
         _new(vocab, merges, kwargs)
       end
+
+      def self.from_file(vocab, merges, **kwargs)
+        _from_file(vocab, merges, kwargs)
+      end
     end
   end
 end
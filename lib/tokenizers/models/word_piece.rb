@@ -4,6 +4,10 @@ module Tokenizers
       def self.new(vocab: nil, **kwargs)
         _new(vocab, kwargs)
       end
+
+      def self.from_file(vocab, **kwargs)
+        _from_file(vocab, kwargs)
+      end
     end
   end
 end
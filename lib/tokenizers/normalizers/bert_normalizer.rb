@@ -4,6 +4,10 @@ module Tokenizers
       def self.new(clean_text: true, handle_chinese_chars: true, strip_accents: nil, lowercase: true)
         _new(clean_text, handle_chinese_chars, strip_accents, lowercase)
       end
+
+      def self.handle_chinese_chars_only
+        new(clean_text: false, handle_chinese_chars: true, strip_accents: false, lowercase: false)
+      end
     end
   end
 end
@@ -0,0 +1,10 @@
+module Tokenizers
+  module Normalizers
+    class Normalizer
+      def to_s(pretty: false)
+        _to_s(pretty)
+      end
+      alias_method :to_str, :to_s
+    end
+  end
+end
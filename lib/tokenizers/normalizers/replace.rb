@@ -0,0 +1,10 @@
+module Tokenizers
+  module Normalizers
+    class Replace
+      # rules are applied in order, so later rules see the result of earlier ones
+      def self.from_rules(rules)
+        Sequence.new(rules.map { |pattern, content| new(pattern, content) })
+      end
+    end
+  end
+end
@@ -1,8 +1,8 @@
 module Tokenizers
   module Normalizers
     class Strip
-      def self.new(left: true, right: true)
-        _new(left, right)
+      def self.new(left: true, right: true, chars: nil)
+        _new(left, right, chars)
       end
     end
   end
@@ -4,6 +4,12 @@ module Tokenizers
       def self.new(add_prefix_space: true, use_regex: true)
         _new(add_prefix_space, use_regex)
       end
+
+      # returns the alphabet characters without a token in the vocab
+      def self.missing_alphabet(vocab)
+        tokens = vocab.is_a?(Hash) ? vocab.keys : vocab.to_a
+        alphabet - tokens
+      end
     end
   end
 end
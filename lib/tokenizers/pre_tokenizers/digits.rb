@@ -1,8 +1,8 @@
 module Tokenizers
   module PreTokenizers
     class Digits
-      def self.new(individual_digits: false)
-        _new(individual_digits)
+      def self.new(individual_digits: false, keep_separators: false, first_only: false)
+        _new(individual_digits, keep_separators, first_only)
       end
     end
   end
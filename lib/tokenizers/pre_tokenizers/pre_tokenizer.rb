@@ -0,0 +1,14 @@
+module Tokenizers
+  module PreTokenizers
+    class PreTokenizer
+      def pre_tokenize_str(sequence, offset_type = :char)
+        _pre_tokenize_str(sequence, offset_type.to_s)
+      end
+
+      def to_s(pretty: false)
+        _to_s(pretty)
+      end
+      alias_method :to_str, :to_s
+    end
+  end
+end
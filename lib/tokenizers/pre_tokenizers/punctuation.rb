@@ -1,8 +1,8 @@
 module Tokenizers
   module PreTokenizers
     class Punctuation
-      def self.new(behavior: "isolated")
-        _new(behavior)
+      def self.new(behavior: "isolated", scope: :all)
+        _new(behavior, scope.to_s)
       end
     end
   end
@@ -0,0 +1,11 @@
+module Tokenizers
+  module PreTokenizers
+    module SplitDelimiterBehavior
+      REMOVED = "removed"
+      ISOLATED = "isolated"
+      MERGED_WITH_PREVIOUS = "merged_with_previous"
+      MERGED_WITH_NEXT = "merged_with_next"
+      CONTIGUOUS = "contiguous"
+    end
+  end
+end
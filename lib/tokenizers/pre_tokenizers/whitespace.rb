@@ -0,0 +1,12 @@
+module Tokenizers
+  module PreTokenizers
+    class Whitespace
+      # the regex used by the upstream pre-tokenizer
+      PATTERN = '\w+|[^\w\s]+'
+
+      def pattern
+        PATTERN
+      end
+    end
+  end
+end
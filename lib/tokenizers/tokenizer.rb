@@ -2,15 +2,35 @@ module Tokenizers
   class Tokenizer
     extend FromPretrained
 
+    # encode_chunks reads and encodes this many chars at a time
+    ENCODE_CHUNKS_WINDOW = 65_536
+
     def to_s(pretty: false)
       _to_s(pretty)
     end
 
-    def save(path, pretty: false)
+    def save(path, pretty: true)
       _save(path, pretty)
     end
 
-    def encode(sequence, pair = nil, is_pretokenized: false, add_special_tokens: true)
+    # a block is called with the progress instead of the trainer showing it (show_progress
+    # is ignored), which is only known while reading the files and when training finishes
+    def train(files, trainer = nil, &block)
+      _train(Array(files).map(&:to_s), trainer, block)
+    end
+
+    def train_from_iterator(iterator, trainer = nil, length: nil)
+      _train_from_iterator(iterator.to_enum, trainer, length)
+    end
+
+    # profile returns the time spent in each stage (in nanoseconds) along with the encoding,
+    # which runs the stages separately, so the times are close to but not exactly those of encode
+    def encode(sequence, pair = nil, is_pretokenized: false, add_special_tokens: true, profile: false)
+      if profile
+        raise ArgumentError, "profile is not supported for pretokenized input" if is_pretokenized
+        return _encode_profile(sequence, pair, add_special_tokens)
+      end
+
       _encode(sequence, pair, is_pretokenized, add_special_tokens)
     end
 
@@ -18,12 +38,101 @@ module Tokenizers
       _encode_batch(input, is_pretokenized, add_special_tokens)
     end
 
-    def decode(ids, skip_special_tokens: true)
-      _decode(ids, skip_special_tokens)
+    def encode_chunks(input, max_length:, chunk_overlap: 0)
+      raise ArgumentError, "max_length must be greater than 0" unless max_length > 0
+      raise ArgumentError, "chunk_overlap must be less than max_length" unless chunk_overlap < max_length
+      return enum_for(__method__, input, max_length: max_length, chunk_overlap: chunk_overlap) unless block_given?
+
+      unless input.respond_to?(:read)
+        require "stringio"
+        input = StringIO.new(input)
+      end
+
+      # padding and truncation would add pad tokens to each window and cut it off,
+      # so windows are encoded on a copy without them
+      tokenizer = self
+      if padding || truncation
+        tokenizer = dup
+        tokenizer.no_padding
+        tokenizer.no_truncation
+      end
+
+      # only a window of the document is read and encoded at a time,
+      # starting at char_base and word_base in the document
+      text = +""
+      char_base = 0
+      word_base = 0
+      window = ENCODE_CHUNKS_WINDOW
+      eof = false
+
+      loop do
+        while !eof && text.length < window
+          part = read_text(input, window)
+          part ? text << part : eof = true
+        end
+
+        # leave the last word for the next window, since it may continue
+        cut = text.length
+        unless eof
+          space = text.rindex(/\s/)
+          cut = space if space && space > 0
+        end
+        encoding = tokenizer.encode(text[0...cut], add_special_tokens: false)
+        word_ids = encoding.word_ids
+        size = word_ids.size
+        word_start = ->(i) { i == 0 || i == size || word_ids[i].nil? || word_ids[i] != word_ids[i - 1] }
+
+        start = 0
+        while start < size
+          stop = start + max_length
+          # the rest doesn't fill a chunk, so it's carried to the next window
+          break if stop >= size && !eof
+
+          if stop >= size
+            stop = size
+          else
+            # end on a word boundary so words aren't split across chunks,
+            # unless a single word is longer than max_length
+            boundary = stop
+            boundary -= 1 until word_start.(boundary)
+            stop = boundary if boundary > start
+          end
+          yield encoding._slice(start, stop, char_base, word_base)
+          break if stop == size
+
+          # consecutive chunks share up to chunk_overlap tokens, starting at a word
+          # (like the truncation stride, but the overflowing encodings from truncation
+          # are cut at a fixed number of tokens, which would split words across chunks)
+          start = [stop - chunk_overlap, start + 1].max
+          start += 1 until start == stop || word_start.(start)
+        end
+        return if eof
+
+        if start < size
+          offset = encoding.offsets[start][0]
+          # keep the whitespace before the word for pre-tokenizers that use it
+          offset -= 1 while offset > 0 && text[offset - 1].match?(/\s/)
+          word_base += word_ids[start] || 0
+        else
+          offset = cut
+        end
+        # read more before encoding again if nothing fit in a chunk
+        window = start == 0 && size > 0 ? window * 2 : ENCODE_CHUNKS_WINDOW
+        text = text[offset..]
+        char_base += offset
+      end
     end
 
-    def decode_batch(sequences, skip_special_tokens: true)
-      _decode_batch(sequences, skip_special_tokens)
+    def decode(ids, skip_special_tokens: true, clean_up_tokenization_spaces: false)
+      _decode(ids, skip_special_tokens, clean_up_tokenization_spaces)
+    end
+
+    def decode_batch(sequences, skip_special_tokens: true, clean_up_tokenization_spaces: false)
+      _decode_batch(sequences, skip_special_tokens, clean_up_tokenization_spaces)
+    end
+
+    def decode_batch_with_offsets(sequences, skip_special_tokens: true)
+      _decode_batch_with_offsets(sequences, skip_special_tokens)
     end
 
     def enable_padding(**options)
@@ -41,5 +150,22 @@ module Tokenizers
     def vocab_size(with_added_tokens: true)
       _vocab_size(with_added_tokens)
     end
+
+    private
+
+    # reads up to length bytes, finishing a char that's cut off
+    def read_text(io, length)
+      part = io.read(length)
+      return if part.nil? || part.empty?
+
+      part = part.b.force_encoding(::Encoding::UTF_8)
+      3.times do
+        break if part.valid_encoding?
+        byte = io.read(1)
+        break unless byte
+        part = (part.b << byte.b).force_encoding(::Encoding::UTF_8)
+      end
+      part
+    end
   end
 end
@@ -1,4 +1,5 @@
 require_relative "test_helper"
+require "json"
 
 class DecoderTest < Minitest::Test
   def test_bpe_decoder
@@ -11,18 +12,41 @@ class DecoderTest < Minitest::Test
     assert_equal "</end>", decoder.suffix
     decoder.suffix = "</w>"
     assert_equal "</w>", decoder.suffix
+
+    assert_equal "hello world", decoder.decode(["hel", "lo</w>", "world</w>"])
   end
 
   def test_byte_fallback
     decoder = Tokenizers::Decoders::ByteFallback.new
     assert_instance_of Tokenizers::Decoders::ByteFallback, decoder
     assert_kind_of Tokenizers::Decoders::ByteFallback, decoder
+
+    assert_equal "\u2581", decoder.decode(["<0xE2>", "<0x96>", "<0x81>"])
+    assert_equal "Hey \u2581!", decoder.decode(["Hey", " ", "<0xE2>", "<0x96>", "<0x81>", "!"])
+    assert_equal "<0x61", decoder.decode(["<0x61"])
+    assert_equal "\uFFFD\uFFFDa", decoder.decode(["<0xE2>", "<0x96>", "a"])
+  end
+
+  def test_byte_fallback_round_trip
+    vocab = {"<unk>" => 0, "a" => 1, "<0xF0>" => 2, "<0x9F>" => 3, "<0xA6>" => 4, "<0x99>" => 5}
+    model = Tokenizers::Models::BPE.new(vocab: vocab, merges: [], unk_token: "<unk>", byte_fallback: true)
+    tokenizer = Tokenizers::Tokenizer.new(model)
+    tokenizer.decoder = Tokenizers::Decoders::ByteFallback.new
+
+    encoded = tokenizer.encode("a🦙")
+    assert_equal ["a", "<0xF0>", "<0x9F>", "<0xA6>", "<0x99>"], encoded.tokens
+    assert_equal "a🦙", tokenizer.decode(encoded.ids)
+
+    model.byte_fallback = false
+    assert_equal ["a", "<unk>"], tokenizer.encode("a🦙").tokens
   end
 
   def test_byte_level
     decoder = Tokenizers::Decoders::ByteLevel.new
     assert_instance_of Tokenizers::Decoders::ByteLevel, decoder
     assert_kind_of Tokenizers::Decoders::Decoder, decoder
+
+    assert_equal "Hello my friend", decoder.decode(["Hello", "Ġmy", "Ġfriend"])
   end
 
   def test_ctc
@@ -47,12 +71,23 @@ class DecoderTest < Minitest::Test
     assert_equal false, decoder.cleanup
     decoder.cleanup = true
     assert_equal true, decoder.cleanup
+
+    decoder = Tokenizers::Decoders::CTC.new
+    assert_equal "helo", decoder.decode(["h", "h", "e", "e", "l", "l", "l", "o"])
+    assert_equal "hello", decoder.decode(["h", "h", "e", "e", "l", "l", "<pad>", "l", "o"])
+    assert_equal "hello world", decoder.decode(["<pad>", "h", "e", "l", "<pad>", "l", "o", "o", "|", "w", "o", "r", "l", "d", "<pad>"])
+
+    decoder = Tokenizers::Decoders::CTC.new(cleanup: false)
+    assert_equal "hello|world", decoder.decode(["h", "e", "l", "<pad>", "l", "o", "|", "|", "w", "o", "r", "l", "d"])
   end
 
   def test_fuse
     decoder = Tokenizers::Decoders::Fuse.new
     assert_instance_of Tokenizers::Decoders::Fuse, decoder
     assert_kind_of Tokenizers::Decoders::Fuse, decoder
+
+    assert_equal "Hello", decoder.decode(["He", "llo"])
+    assert_equal "Hey friend", decoder.decode(["Hey", " ", "friend"])
   end
 
   def test_metaspace
@@ -69,12 +104,47 @@ class DecoderTest < Minitest::Test
     assert_equal false, decoder.add_prefix_space
     decoder.add_prefix_space = true
     assert_equal true, decoder.add_prefix_space
+
+    decoder = Tokenizers::Decoders::Metaspace.new
+    assert_equal "Hey friend!", decoder.decode(["\u2581Hey", "\u2581friend!"])
   end
 
   def test_replace
     decoder = Tokenizers::Decoders::Replace.new('abc', 'xyz')
     assert_instance_of Tokenizers::Decoders::Replace, decoder
     assert_kind_of Tokenizers::Decoders::Replace, decoder
+
+    decoder = Tokenizers::Decoders::Replace.new("\u2581", " ")
+    assert_equal " Hello world", decoder.decode(["\u2581Hello", "\u2581world"])
+
+    strip = Tokenizers::Decoders::Strip.new(content: " ", start: 1, stop: 0)
+    assert_equal "Hello world", strip.decode([decoder.decode(["\u2581Hello", "\u2581world"])])
+
+    decoder = Tokenizers::Decoders::Replace.new(Tokenizers::Regex.new("\u2581+"), " ")
+    assert_equal " a b", decoder.decode(["\u2581\u2581a", "\u2581b"])
+  end
+
+  def test_sequence
+    decoder = Tokenizers::Decoders::Sequence.new([
+      Tokenizers::Decoders::Replace.new("\u2581", " "),
+      Tokenizers::Decoders::ByteFallback.new,
+      Tokenizers::Decoders::Fuse.new,
+      Tokenizers::Decoders::Strip.new(content: " ", start: 1, stop: 0)
+    ])
+    assert_instance_of Tokenizers::Decoders::Sequence, decoder
+    assert_kind_of Tokenizers::Decoders::Decoder, decoder
+
+    assert_equal "Hello world!", decoder.decode(["\u2581Hello", "\u2581world", "<0x21>"])
+
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: {"[UNK]" => 0}, unk_token: "[UNK]"))
+    tokenizer.decoder = decoder
+    config = JSON.parse(tokenizer.to_s)
+    assert_equal "Sequence", config["decoder"]["type"]
+    assert_equal ["Replace", "ByteFallback", "Fuse", "Strip"], config["decoder"]["decoders"].map { |d| d["type"] }
+
+    tokenizer = Tokenizers.from_str(JSON.generate(config))
+    assert_instance_of Tokenizers::Decoders::Sequence, tokenizer.decoder
+    assert_equal "Hello world!", tokenizer.decoder.decode(["\u2581Hello", "\u2581world", "<0x21>"])
   end
 
   def test_strip
@@ -103,6 +173,12 @@ class DecoderTest < Minitest::Test
     assert_equal 12, decoder.stop
     decoder.stop = 16
     assert_equal 16, decoder.stop
+
+    decoder = Tokenizers::Decoders::Strip.new(content: "\u2581", start: 1)
+    assert_equal "HelloHow", decoder.decode(["\u2581Hello", "How"])
+
+    decoder = Tokenizers::Decoders::Strip.new(content: "-", stop: 2)
+    assert_equal "abc", decoder.decode(["a--", "b-", "c"])
   end
 
   def test_word_piece
@@ -119,5 +195,14 @@ class DecoderTest < Minitest::Test
     assert_equal false, decoder.cleanup
     decoder.cleanup = true
     assert_equal true, decoder.cleanup
+
+    decoder = Tokenizers::Decoders::WordPiece.new
+    assert_equal "I'm going to tokenize!", decoder.decode(["I", "'m", "go", "##ing", "to", "token", "##ize", "!"])
+
+    decoder = Tokenizers::Decoders::WordPiece.new(cleanup: false)
+    assert_equal "I 'm going to tokenize !", decoder.decode(["I", "'m", "go", "##ing", "to", "token", "##ize", "!"])
+
+    decoder = Tokenizers::Decoders::WordPiece.new(prefix: "__")
+    assert_equal "unbelievable, isn't it?", decoder.decode(["un", "__believ", "__able", ",", "is", "n't", "it", "?"])
   end
 end
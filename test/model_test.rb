@@ -1,4 +1,6 @@
 require_relative "test_helper"
+require "json"
+require "tmpdir"
 
 class ModelTest < Minitest::Test
   def test_bpe
@@ -44,6 +46,84 @@ class ModelTest < Minitest::Test
     assert_equal false, model.byte_fallback
   end
 
+  def test_bpe_dropout
+    vocab = {"h" => 0, "e" => 1, "l" => 2, "o" => 3, "he" => 4, "ll" => 5, "llo" => 6, "hello" => 7}
+    merges = [["h", "e"], ["l", "l"], ["ll", "o"], ["he", "llo"]]
+
+    model = Tokenizers::Models::BPE.new(vocab: vocab, merges: merges)
+    assert_nil model.dropout
+    assert_equal [["hello", 7]], model.tokenize("hello")
+
+    # same as no dropout
+    model = Tokenizers::Models::BPE.new(vocab: vocab, merges: merges, dropout: 0.0)
+    assert_nil model.dropout
+    10.times do
+      assert_equal [["hello", 7]], model.tokenize("hello")
+    end
+
+    # upstream rejects 0.0 when loading
+    tokenizer = Tokenizers::Tokenizer.new(model)
+    tokenizer = Tokenizers::Tokenizer.from_str(tokenizer.to_s)
+    assert_nil tokenizer.model.dropout
+    assert_equal ["hello"], tokenizer.encode("hello").tokens
+
+    # every merge is skipped
+    model.dropout = 1.0
+    assert_equal [["h", 0], ["e", 1], ["l", 2], ["l", 2], ["o", 3]], model.tokenize("hello")
+
+    model.dropout = nil
+    assert_equal [["hello", 7]], model.tokenize("hello")
+
+    model.dropout = 0.0
+    assert_nil model.dropout
+
+    assert_raises(ArgumentError) { model.dropout = 1.5 }
+    error = assert_raises(ArgumentError) do
+      Tokenizers::Models::BPE.new(vocab: vocab, merges: merges, dropout: -0.1)
+    end
+    assert_equal "dropout must be between 0 and 1", error.message
+  end
+
+  def test_bpe_continuing_subword_prefix
+    vocab = {"a" => 0, "b" => 1, "##b" => 2, "b</w>" => 3}
+    model = Tokenizers::Models::BPE.new(vocab: vocab, merges: [])
+    assert_nil model.continuing_subword_prefix
+    assert_nil model.end_of_word_suffix
+    assert_equal [["a", 0], ["b", 1]], model.tokenize("ab")
+
+    model.continuing_subword_prefix = "##"
+    assert_equal [["a", 0], ["##b", 2]], model.tokenize("ab")
+
+    model.continuing_subword_prefix = nil
+    model.end_of_word_suffix = "</w>"
+    assert_equal [["a", 0], ["b</w>", 3]], model.tokenize("ab")
+  end
+
+  def test_bpe_from_file
+    vocab = {"h" => 0, "e" => 1, "l" => 2, "o" => 3, "he" => 4, "ll" => 5, "llo" => 6, "hello" => 7}
+    merges = [["h", "e"], ["l", "l"], ["ll", "o"], ["he", "llo"]]
+
+    Dir.mktmpdir do |dir|
+      vocab_path = File.join(dir, "vocab.json")
+      merges_path = File.join(dir, "merges.txt")
+      File.write(vocab_path, JSON.generate(vocab))
+      File.write(merges_path, "#version: 0.2\n" + merges.map { |m| m.join(" ") }.join("\n") + "\n")
+
+      model = Tokenizers::Models::BPE.from_file(vocab_path, merges_path, unk_token: "o")
+      assert_instance_of Tokenizers::Models::BPE, model
+      assert_equal "o", model.unk_token
+
+      tokenizer = Tokenizers::Tokenizer.new(model)
+      encoded = tokenizer.encode("hello")
+      assert_equal ["hello"], encoded.tokens
+      assert_equal [7], encoded.ids
+      assert_equal "hello", tokenizer.decode(encoded.ids)
+    end
+
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(vocab: vocab, merges: merges))
+    assert_equal ["he", "ll"], tokenizer.encode("hell").tokens
+  end
+
   def test_word_level
     model = Tokenizers::Models::WordLevel.new
     assert_instance_of Tokenizers::Models::WordLevel, model
@@ -57,6 +137,27 @@ class ModelTest < Minitest::Test
     assert_equal "[PAD]", model.unk_token
   end
 
+  def test_word_level_tokenize
+    vocab = {"[UNK]" => 0, "hello" => 1, "world" => 2}
+    model = Tokenizers::Models::WordLevel.new(vocab: vocab, unk_token: "[UNK]")
+
+    assert_equal [["hello", 1]], model.tokenize("hello")
+    assert_equal [["[UNK]", 0]], model.tokenize("goodbye")
+    assert_equal 3, model.vocab_size
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "vocab.json")
+      File.write(path, JSON.generate(vocab))
+
+      model = Tokenizers::Models::WordLevel.from_file(path, unk_token: "[UNK]")
+      assert_equal [["world", 2]], model.tokenize("world")
+      assert_equal 3, model.vocab_size
+    end
+
+    model = Tokenizers::Models::WordLevel.new(vocab: {"hello" => 0}, unk_token: "[UNK]")
+    assert_raises(Tokenizers::Error) { model.tokenize("goodbye") }
+  end
+
   def test_word_piece
     model = Tokenizers::Models::WordPiece.new
     assert_instance_of Tokenizers::Models::WordPiece, model
@@ -83,6 +184,27 @@ class ModelTest < Minitest::Test
     assert_equal "#xxx#", model.continuing_subword_prefix
   end
 
+  def test_word_piece_tokenize
+    vocab = {"[UNK]" => 0, "hello" => 1, "hel" => 2, "##lo" => 3}
+    model = Tokenizers::Models::WordPiece.new(vocab: vocab, unk_token: "[UNK]", max_input_chars_per_word: 5)
+
+    assert_equal [["hello", 1]], model.tokenize("hello")
+    assert_equal [["hel", 2], ["##lo", 3]], Tokenizers::Models::WordPiece.new(vocab: {"[UNK]" => 0, "hel" => 2, "##lo" => 3}).tokenize("hello")
+    assert_equal [["[UNK]", 0]], model.tokenize("hellohello")
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "vocab.txt")
+      File.write(path, vocab.keys.join("\n") + "\n")
+
+      model = Tokenizers::Models::WordPiece.from_file(path, max_input_chars_per_word: 5)
+      assert_equal 4, model.vocab_size
+      assert_equal "[UNK]", model.unk_token
+      assert_equal "##", model.continuing_subword_prefix
+      assert_equal [["hello", 1]], model.tokenize("hello")
+      assert_equal [["[UNK]", 0]], model.tokenize("hellohello")
+    end
+  end
+
   def test_unigram
     model = Tokenizers::Models::Unigram.new
     assert_instance_of Tokenizers::Models::Unigram, model
@@ -90,4 +212,33 @@ class ModelTest < Minitest::Test
 
     Tokenizers::Models::Unigram.new(vocab: [["a", 0.117], ["b", 0.786]])
   end
+
+  def test_unigram_tokenize
+    vocab = [
+      ["<unk>", 0.0],
+      ["東京", -2.0],
+      ["東", -5.0],
+      ["京", -5.0],
+      ["都", -3.0],
+      ["京都", -4.0],
+      ["に", -1.0],
+      ["行く", -2.0],
+      ["行", -6.0],
+      ["く", -6.0]
+    ]
+    model = Tokenizers::Models::Unigram.new(vocab: vocab, unk_id: 0)
+    assert_equal [["東京", 1], ["都", 4], ["に", 6], ["行く", 7]], model.tokenize("東京都に行く")
+    assert_equal 10, model.vocab_size
+
+    tokenizer = Tokenizers::Tokenizer.new(model)
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "tokenizer.json")
+      tokenizer.save(path)
+
+      tokenizer = Tokenizers::Tokenizer.from_file(path)
+      encoded = tokenizer.encode("東京都に行く")
+      assert_equal ["東京", "都", "に", "行く"], encoded.tokens
+      assert_equal [1, 4, 6, 7], encoded.ids
+    end
+  end
 end
@@ -1,4 +1,5 @@
 require_relative "test_helper"
+require "json"
 
 class NormalizerTest < Minitest::Test
   def test_normalize_str
@@ -6,6 +7,25 @@ class NormalizerTest < Minitest::Test
     assert_equal "Hello how are u?", normalizer.normalize_str("Héllò hôw are ü?")
   end
 
+  def test_normalize_str_large_input
+    normalizer = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFD.new, Tokenizers::Normalizers::StripAccents.new])
+    input = ("Héllò hôw are ü? " * 300_000).freeze
+    assert_operator input.bytesize, :>, 5_000_000
+    assert_equal "Hello how are u? " * 300_000, normalizer.normalize_str(input)
+  end
+
+  def test_normalize_with_changes
+    normalizer = Tokenizers::Normalizers::NFKC.new
+    assert_equal ["fine", [[[0, 1], [0, 2]]]], normalizer.normalize_with_changes("ﬁne")
+    assert_equal ["the ﬁ", []], Tokenizers::Normalizers::NFC.new.normalize_with_changes("the ﬁ")
+
+    normalizer = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFD.new, Tokenizers::Normalizers::StripAccents.new])
+    assert_equal ["Hello u", [[[1, 2], [1, 2]], [[6, 7], [6, 7]]]], normalizer.normalize_with_changes("Héllo ü")
+
+    normalizer = Tokenizers::Normalizers::Strip.new
+    assert_equal ["hi", [[[0, 2], [0, 0]], [[4, 5], [2, 2]]]], normalizer.normalize_with_changes("  hi ")
+  end
+
   def test_bert_normalizer
     normalizer = Tokenizers::Normalizers::BertNormalizer.new
     assert_instance_of Tokenizers::Normalizers::BertNormalizer, normalizer
@@ -35,6 +55,89 @@ class NormalizerTest < Minitest::Test
     assert_equal true, normalizer.lowercase
   end
 
+  def test_bert_normalizer_handle_chinese_chars_only
+    normalizer = Tokenizers::Normalizers::BertNormalizer.handle_chinese_chars_only
+    assert_instance_of Tokenizers::Normalizers::BertNormalizer, normalizer
+    assert_equal false, normalizer.clean_text
+    assert_equal true, normalizer.handle_chinese_chars
+    assert_equal false, normalizer.strip_accents
+    assert_equal false, normalizer.lowercase
+
+    assert_equal "Héllo  你  好 !\t", normalizer.normalize_str("Héllo 你好!\t")
+
+    normalized, changes = normalizer.normalize_with_changes("é你好!")
+    assert_equal "é 你  好 !", normalized
+    assert_equal [[[1, 3], [1, 7]]], changes
+  end
+
+  def test_invalid_utf8
+    normalizer = Tokenizers::Normalizers::Lowercase.new
+    error = assert_raises(Tokenizers::Error) { normalizer.normalize_str("\xFF".b) }
+    assert_equal "Invalid UTF-8 in input at byte offset 0: \"\\xFF\"", error.message
+
+    error = assert_raises(Tokenizers::Error) { normalizer.normalize_with_changes("ok\xE2\x96") }
+    assert_equal "Invalid UTF-8 in input at byte offset 2: \"\\xE2\\x96\"", error.message
+
+    assert_equal "héllo", normalizer.normalize_str("HÉLLO".encode("ISO-8859-1"))
+  end
+
+  def test_equality
+    assert_equal Tokenizers::Normalizers::Lowercase.new, Tokenizers::Normalizers::Lowercase.new
+    refute_equal Tokenizers::Normalizers::Lowercase.new, Tokenizers::Normalizers::NFC.new
+    refute_equal Tokenizers::Normalizers::Lowercase.new, "Lowercase"
+
+    a = Tokenizers::Normalizers::BertNormalizer.new(lowercase: false)
+    b = Tokenizers::Normalizers::BertNormalizer.new(lowercase: false)
+    assert_equal a, b
+    assert a.eql?(b)
+    assert_equal a.hash, b.hash
+    b.lowercase = true
+    refute_equal a, b
+
+    a = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFD.new, Tokenizers::Normalizers::Lowercase.new])
+    b = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFD.new, Tokenizers::Normalizers::Lowercase.new])
+    c = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::Lowercase.new, Tokenizers::Normalizers::NFD.new])
+    assert_equal a, b
+    refute_equal a, c
+    assert_equal 2, [a, b, c].uniq.size
+  end
+
+  def test_inspect
+    normalizer = Tokenizers::Normalizers::BertNormalizer.new
+    assert_equal "#<Tokenizers::Normalizers::BertNormalizer clean_text=true handle_chinese_chars=true lowercase=true strip_accents=nil>", normalizer.inspect
+
+    normalizer = Tokenizers::Normalizers::Lowercase.new
+    assert_equal "#<Tokenizers::Normalizers::Lowercase>", normalizer.inspect
+
+    normalizer = Tokenizers::Normalizers::Prepend.new(prepend: "\u2581")
+    assert_equal "#<Tokenizers::Normalizers::Prepend prepend=\"\u2581\">", normalizer.inspect
+
+    normalizer = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFD.new, Tokenizers::Normalizers::Lowercase.new])
+    assert_equal "#<Tokenizers::Normalizers::Sequence normalizers=[NFD, Lowercase]>", normalizer.inspect
+  end
+
+  def test_to_s_from_str
+    normalizer = Tokenizers::Normalizers::Sequence.new([
+      Tokenizers::Normalizers::NFD.new,
+      Tokenizers::Normalizers::StripAccents.new,
+      Tokenizers::Normalizers::BertNormalizer.new(lowercase: false)
+    ])
+    json = normalizer.to_s
+    assert_equal json, normalizer.to_str
+    assert_equal "Sequence", JSON.parse(json)["type"]
+    assert_includes normalizer.to_s(pretty: true), "\n"
+
+    new_normalizer = Tokenizers::Normalizers::Normalizer.from_str(json)
+    assert_instance_of Tokenizers::Normalizers::Sequence, new_normalizer
+    assert_equal normalizer, new_normalizer
+    assert_equal "Hello", new_normalizer.normalize_str("Héllo")
+
+    assert_instance_of Tokenizers::Normalizers::Lowercase, Tokenizers::Normalizers::Normalizer.from_str('{"type": "Lowercase"}')
+    assert_raises(Tokenizers::Error) do
+      Tokenizers::Normalizers::Normalizer.from_str('{"type": "Unknown"}')
+    end
+  end
+
   def test_lowercase
     normalizer = Tokenizers::Normalizers::Lowercase.new
     assert_instance_of Tokenizers::Normalizers::Lowercase, normalizer
@@ -77,6 +180,38 @@ class NormalizerTest < Minitest::Test
     assert_kind_of Tokenizers::Normalizers::Replace, normalizer
   end
 
+  def test_replace_from_rules
+    normalizer = Tokenizers::Normalizers::Replace.from_rules([
+      ["``", "\""],
+      [/\s+/, " "],
+      ["\" ", "\""],
+      [/"/, "'"]
+    ])
+    assert_instance_of Tokenizers::Normalizers::Sequence, normalizer
+    assert_equal "'hello'world", normalizer.normalize_str("``hello\"  \n world")
+
+    assert_equal "x", Tokenizers::Normalizers::Replace.from_rules([["a", "b"], ["b", "x"]]).normalize_str("a")
+    assert_equal "b", Tokenizers::Normalizers::Replace.from_rules([["b", "x"], ["a", "b"]]).normalize_str("a")
+    assert_equal "a", Tokenizers::Normalizers::Replace.from_rules([]).normalize_str("a")
+  end
+
+  def test_replace_regexp
+    normalizer = Tokenizers::Normalizers::Replace.new(/\s+/, " ")
+    assert_equal "hello big world", normalizer.normalize_str("hello \t big\n\nworld")
+
+    normalizer = Tokenizers::Normalizers::Replace.new(/world/i, "there")
+    assert_equal "hello there", normalizer.normalize_str("hello WoRlD")
+
+    normalizer = Tokenizers::Normalizers::Replace.new(/a.b/m, "-")
+    assert_equal "-", normalizer.normalize_str("a\nb")
+
+    normalizer = Tokenizers::Normalizers::Replace.new(Tokenizers::Regex.new("\\d+"), "#")
+    assert_equal "call #", normalizer.normalize_str("call 911")
+
+    error = assert_raises(ArgumentError) { Tokenizers::Normalizers::Replace.new(/a/n, "b") }
+    assert_equal "Unsupported Regexp options: Regexp::NOENCODING", error.message
+  end
+
   def test_prepend
     normalizer = Tokenizers::Normalizers::Prepend.new
     assert_instance_of Tokenizers::Normalizers::Prepend, normalizer
@@ -103,9 +238,73 @@ class NormalizerTest < Minitest::Test
     assert_equal true, normalizer.right
   end
 
+  def test_strip_all_whitespace
+    [[true, true], [true, false], [false, true]].each do |left, right|
+      [nil, " \t\u3000"].each do |chars|
+        normalizer = Tokenizers::Normalizers::Strip.new(left: left, right: right, chars: chars)
+        assert_equal "", normalizer.normalize_str(" \t\u3000")
+        assert_equal ["", [[[0, 3], [0, 0]]]], normalizer.normalize_with_changes(" \t\u3000")
+      end
+    end
+
+    normalizer = Tokenizers::Normalizers::Strip.new(left: true, right: false)
+    assert_equal ["é  ", [[[0, 2], [0, 0]]]], normalizer.normalize_with_changes("  é  ")
+    normalizer = Tokenizers::Normalizers::Strip.new(left: false, right: true)
+    assert_equal ["  é", [[[3, 5], [3, 3]]]], normalizer.normalize_with_changes("  é  ")
+
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: {"[UNK]" => 0, "é" => 1}, unk_token: "[UNK]"))
+    tokenizer.normalizer = normalizer
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::WhitespaceSplit.new
+    assert_empty tokenizer.encode("   ").ids
+    encoding = tokenizer.encode("  é  ")
+    assert_equal ["é"], encoding.tokens
+    assert_equal [[2, 3]], encoding.offsets
+  end
+
+  def test_strip_chars
+    normalizer = Tokenizers::Normalizers::Strip.new
+    assert_nil normalizer.chars
+    assert_equal "hello", normalizer.normalize_str("  hello ")
+
+    normalizer = Tokenizers::Normalizers::Strip.new(chars: "\u200B")
+    assert_instance_of Tokenizers::Normalizers::Strip, normalizer
+    assert_equal "\u200B", normalizer.chars
+    assert_equal "hello\u200B world ", normalizer.normalize_str("\u200B\u200Bhello\u200B world \u200B")
+    assert_equal "", normalizer.normalize_str("\u200B\u200B")
+
+    normalizer.left = false
+    assert_equal "\u200Bhello", normalizer.normalize_str("\u200Bhello\u200B")
+    assert_equal "\u200B", normalizer.chars
+
+    normalizer.chars = nil
+    assert_nil normalizer.chars
+    assert_equal false, normalizer.left
+    assert_equal " hello", normalizer.normalize_str(" hello ")
+  end
+
   def test_strip_accents
     normalizer = Tokenizers::Normalizers::StripAccents.new
     assert_instance_of Tokenizers::Normalizers::StripAccents, normalizer
     assert_kind_of Tokenizers::Normalizers::StripAccents, normalizer
   end
+
+  def test_unidecode
+    normalizer = Tokenizers::Normalizers::Unidecode.new
+    assert_instance_of Tokenizers::Normalizers::Unidecode, normalizer
+    assert_kind_of Tokenizers::Normalizers::Normalizer, normalizer
+
+    assert_equal "naive cafe Strasse AEsir Moskva", normalizer.normalize_str("naïve café Straße Æsir Москва")
+
+    # multi-char expansions map back to the char they replace
+    assert_equal ["Strasse AEsir", [[[4, 5], [4, 6]], [[7, 8], [8, 10]]]], normalizer.normalize_with_changes("Straße Æsir")
+  end
+
+  def test_uppercase
+    normalizer = Tokenizers::Normalizers::Uppercase.new
+    assert_instance_of Tokenizers::Normalizers::Uppercase, normalizer
+    assert_kind_of Tokenizers::Normalizers::Normalizer, normalizer
+
+    assert_equal "STRASSE", normalizer.normalize_str("straße")
+    assert_equal "ΑΘΉΝΑ", normalizer.normalize_str("αθήνα")
+  end
 end
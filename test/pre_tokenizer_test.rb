@@ -1,4 +1,5 @@
 require_relative "test_helper"
+require "json"
 
 class PreTokenizerTest < Minitest::Test
   def test_pre_tokenize_str
@@ -7,12 +8,87 @@ class PreTokenizerTest < Minitest::Test
     assert_equal ["Hello", [0, 5]], tokens.first
   end
 
+  def test_pre_tokenize_str_offset_type
+    pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+    assert_equal [["I", [0, 1]], ["🤗", [2, 3]], ["you", [4, 7]]], pre_tokenizer.pre_tokenize_str("I 🤗 you")
+    assert_equal [["I", [0, 1]], ["🤗", [2, 3]], ["you", [4, 7]]], pre_tokenizer.pre_tokenize_str("I 🤗 you", :char)
+    assert_equal [["I", [0, 1]], ["🤗", [2, 6]], ["you", [7, 10]]], pre_tokenizer.pre_tokenize_str("I 🤗 you", :byte)
+    assert_raises(ArgumentError) { pre_tokenizer.pre_tokenize_str("I 🤗 you", :word) }
+  end
+
   def test_pre_tokenize_str_sequence
     pre_tokenizer = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Digits.new(individual_digits: true)])
     expected = [["Call", [0, 4]], ["9", [5, 6]], ["1", [6, 7]], ["1", [7, 8]], ["!", [8, 9]]]
     assert_equal expected, pre_tokenizer.pre_tokenize_str("Call 911!")
   end
 
+  def test_invalid_utf8
+    pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+    error = assert_raises(Tokenizers::Error) { pre_tokenizer.pre_tokenize_str("I \xFF you".b) }
+    assert_equal "Invalid UTF-8 in input at byte offset 2: \"\\xFF\"", error.message
+  end
+
+  def test_equality
+    assert_equal Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Whitespace.new
+    refute_equal Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::WhitespaceSplit.new
+    refute_equal Tokenizers::PreTokenizers::Whitespace.new, nil
+
+    a = Tokenizers::PreTokenizers::Digits.new
+    b = Tokenizers::PreTokenizers::Digits.new
+    assert_equal a, b
+    assert_equal a.hash, b.hash
+    b.individual_digits = true
+    refute_equal a, b
+
+    a = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Digits.new])
+    b = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Digits.new])
+    assert_equal a, b
+    assert a.eql?(b)
+    b[1].individual_digits = true
+    refute_equal a, b
+  end
+
+  def test_inspect
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(individual_digits: true)
+    assert_equal "#<Tokenizers::PreTokenizers::Digits individual_digits=true>", pre_tokenizer.inspect
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Digits.new])
+    assert_equal "#<Tokenizers::PreTokenizers::Sequence pretokenizers=[Whitespace, Digits]>", pre_tokenizer.inspect
+  end
+
+  def test_to_s_from_str
+    pre_tokenizer = Tokenizers::PreTokenizers::Sequence.new([
+      Tokenizers::PreTokenizers::Whitespace.new,
+      Tokenizers::PreTokenizers::Digits.new(individual_digits: true)
+    ])
+    json = pre_tokenizer.to_s
+    assert_equal json, pre_tokenizer.to_str
+    assert_equal "Sequence", JSON.parse(json)["type"]
+
+    new_pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(json)
+    assert_instance_of Tokenizers::PreTokenizers::Sequence, new_pre_tokenizer
+    assert_equal pre_tokenizer, new_pre_tokenizer
+    assert_equal ["Call", "9", "1", "1"], new_pre_tokenizer.pre_tokenize_str("Call 911").map(&:first)
+
+    assert_raises(Tokenizers::Error) do
+      Tokenizers::PreTokenizers::PreTokenizer.from_str("{")
+    end
+  end
+
+  def test_sequence_index
+    pre_tokenizer = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::Whitespace.new, Tokenizers::PreTokenizers::Digits.new])
+    assert_equal 2, pre_tokenizer.length
+    assert_instance_of Tokenizers::PreTokenizers::Whitespace, pre_tokenizer[0]
+    assert_instance_of Tokenizers::PreTokenizers::Digits, pre_tokenizer[1]
+    assert_instance_of Tokenizers::PreTokenizers::Digits, pre_tokenizer[-1]
+    assert_raises(IndexError) { pre_tokenizer[2] }
+    assert_raises(IndexError) { pre_tokenizer[-3] }
+
+    assert_equal ["Call", "911"], pre_tokenizer.pre_tokenize_str("Call 911").map(&:first)
+    pre_tokenizer[1].individual_digits = true
+    assert_equal ["Call", "9", "1", "1"], pre_tokenizer.pre_tokenize_str("Call 911").map(&:first)
+  end
+
   def test_byte_level
     pre_tokenizer = Tokenizers::PreTokenizers::ByteLevel.new
     assert_instance_of Tokenizers::PreTokenizers::ByteLevel, pre_tokenizer
@@ -32,6 +108,18 @@ class PreTokenizerTest < Minitest::Test
     assert 256, alphabet.size
   end
 
+  def test_byte_level_missing_alphabet
+    vocab = Tokenizers.from_pretrained("gpt2").vocab
+    assert_equal [], Tokenizers::PreTokenizers::ByteLevel.missing_alphabet(vocab)
+
+    vocab.delete("Ġ")
+    vocab.delete("Ã")
+    assert_equal ["Ã", "Ġ"], Tokenizers::PreTokenizers::ByteLevel.missing_alphabet(vocab).sort
+
+    alphabet = Tokenizers::PreTokenizers::ByteLevel.alphabet
+    assert_equal alphabet.sort, Tokenizers::PreTokenizers::ByteLevel.missing_alphabet(["hello", "world"]).sort
+  end
+
   def test_char_delimiter_split
     pre_tokenizer = Tokenizers::PreTokenizers::CharDelimiterSplit.new('a')
     assert_instance_of Tokenizers::PreTokenizers::CharDelimiterSplit, pre_tokenizer
@@ -42,6 +130,30 @@ class PreTokenizerTest < Minitest::Test
     assert_equal 'b', pre_tokenizer.delimiter
   end
 
+  def test_char_delimiter_split_multiple_chars
+    pre_tokenizer = Tokenizers::PreTokenizers::CharDelimiterSplit.new("::")
+    assert_instance_of Tokenizers::PreTokenizers::CharDelimiterSplit, pre_tokenizer
+    assert_equal "::", pre_tokenizer.delimiter
+    assert_equal [["a", [0, 1]], ["b:c", [3, 6]], ["d", [8, 9]]], pre_tokenizer.pre_tokenize_str("a::b:c::d")
+
+    json = pre_tokenizer.to_s
+    assert_equal({"type" => "CharDelimiterSplit", "delimiter" => "::"}, JSON.parse(json))
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(json)
+    assert_instance_of Tokenizers::PreTokenizers::CharDelimiterSplit, pre_tokenizer
+    assert_equal "::", pre_tokenizer.delimiter
+
+    pre_tokenizer.delimiter = ":"
+    assert_equal ":", pre_tokenizer.delimiter
+    assert_equal [["a", [0, 1]], ["b", [2, 3]]], pre_tokenizer.pre_tokenize_str("a:b")
+    pre_tokenizer.delimiter = "--"
+    assert_equal [["a:b", [0, 3]], ["c", [5, 6]]], pre_tokenizer.pre_tokenize_str("a:b--c")
+
+    pre_tokenizer = Tokenizers::PreTokenizers::CharDelimiterSplit.new(":")
+    assert_equal [["a", [0, 1]], ["b", [2, 3]]], pre_tokenizer.pre_tokenize_str("a:b")
+
+    assert_raises(ArgumentError) { Tokenizers::PreTokenizers::CharDelimiterSplit.new("") }
+  end
+
   def test_digits
     pre_tokenizer = Tokenizers::PreTokenizers::Digits.new
     assert_instance_of Tokenizers::PreTokenizers::Digits, pre_tokenizer
@@ -54,6 +166,115 @@ class PreTokenizerTest < Minitest::Test
     assert_equal false, pre_tokenizer.individual_digits
   end
 
+  def test_digits_pre_tokenize_str
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new
+    assert_equal ["abc", "123", "def", "456"], pre_tokenizer.pre_tokenize_str("abc123def456").map(&:first)
+
+    # single digits are the same either way
+    assert_equal ["v", "1", ".", "2", ".", "3"], pre_tokenizer.pre_tokenize_str("v1.2.3").map(&:first)
+    assert_equal ["v", "1", ".", "23", ".", "456"], pre_tokenizer.pre_tokenize_str("v1.23.456").map(&:first)
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(individual_digits: true)
+    assert_equal ["v", "1", ".", "2", ".", "3"], pre_tokenizer.pre_tokenize_str("v1.2.3").map(&:first)
+    assert_equal ["v", "1", ".", "2", "3", ".", "4", "5", "6"], pre_tokenizer.pre_tokenize_str("v1.23.456").map(&:first)
+  end
+
+  def test_digits_first_only
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(first_only: true)
+    assert_instance_of Tokenizers::PreTokenizers::Digits, pre_tokenizer
+    assert_equal true, pre_tokenizer.first_only
+    assert_equal [["abc", [0, 3]], ["123", [3, 6]], ["def456", [6, 12]]], pre_tokenizer.pre_tokenize_str("abc123def456")
+    assert_equal ["v", "1", ".23.456"], pre_tokenizer.pre_tokenize_str("v1.23.456").map(&:first)
+
+    pre_tokenizer.individual_digits = true
+    assert_equal ["abc", "1", "2", "3", "def456"], pre_tokenizer.pre_tokenize_str("abc123def456").map(&:first)
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(keep_separators: true, first_only: true)
+    assert_equal ["v", "1.23", " and 4.56"], pre_tokenizer.pre_tokenize_str("v1.23 and 4.56").map(&:first)
+
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(pre_tokenizer.to_s)
+    assert_equal true, pre_tokenizer.first_only
+    assert_equal true, pre_tokenizer.keep_separators
+
+    pre_tokenizer.first_only = false
+    assert_equal ["v", "1.23", " and ", "4.56"], pre_tokenizer.pre_tokenize_str("v1.23 and 4.56").map(&:first)
+    pre_tokenizer.keep_separators = false
+    assert_equal false, pre_tokenizer.first_only
+    assert_equal ["v", "1", ".", "23", " and ", "4", ".", "56"], pre_tokenizer.pre_tokenize_str("v1.23 and 4.56").map(&:first)
+  end
+
+  def test_digits_keep_separators
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new
+    assert_equal false, pre_tokenizer.keep_separators
+    assert_equal ["price: ", "1", ",", "234", ".", "56"], pre_tokenizer.pre_tokenize_str("price: 1,234.56").map(&:first)
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(keep_separators: true)
+    assert_instance_of Tokenizers::PreTokenizers::Digits, pre_tokenizer
+    assert_equal true, pre_tokenizer.keep_separators
+    assert_equal [["price: ", [0, 7]], ["1,234.56", [7, 15]]], pre_tokenizer.pre_tokenize_str("price: 1,234.56")
+    assert_equal ["costs ", "5", ". Then ", "3", ", ", "4"], pre_tokenizer.pre_tokenize_str("costs 5. Then 3, 4").map(&:first)
+    assert_equal ["v", "1.2.3", "-", "٣.١٤"], pre_tokenizer.pre_tokenize_str("v1.2.3-٣.١٤").map(&:first)
+
+    # separators are only kept between runs of digits
+    error = assert_raises(ArgumentError) do
+      pre_tokenizer.individual_digits = true
+    end
+    assert_equal "keep_separators can't be used with individual_digits", error.message
+    assert_equal false, pre_tokenizer.individual_digits
+    assert_equal ["1,2"], pre_tokenizer.pre_tokenize_str("1,2").map(&:first)
+
+    assert_raises(ArgumentError) do
+      Tokenizers::PreTokenizers::Digits.new(individual_digits: true, keep_separators: true)
+    end
+    pre_tokenizer = Tokenizers::PreTokenizers::Digits.new(individual_digits: true)
+    assert_raises(ArgumentError) do
+      pre_tokenizer.keep_separators = true
+    end
+    assert_equal false, pre_tokenizer.keep_separators
+
+    # loads as upstream Digits, which ignores keep_separators
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(%q({"type":"Digits","individual_digits":true,"keep_separators":true}))
+    assert_equal true, pre_tokenizer.individual_digits
+    assert_equal false, pre_tokenizer.keep_separators
+
+    sequence = Tokenizers::PreTokenizers::Sequence.new([Tokenizers::PreTokenizers::WhitespaceSplit.new, Tokenizers::PreTokenizers::Digits.new])
+    assert_equal ["pi", "3", ".", "14"], sequence.pre_tokenize_str("pi 3.14").map(&:first)
+    sequence[1].keep_separators = true
+    assert_equal true, sequence[1].keep_separators
+    assert_equal ["pi", "3.14"], sequence.pre_tokenize_str("pi 3.14").map(&:first)
+
+    json = sequence.to_s
+    assert_equal ["pi", "3.14"], Tokenizers::PreTokenizers::PreTokenizer.from_str(json).pre_tokenize_str("pi 3.14").map(&:first)
+    assert_instance_of Tokenizers::PreTokenizers::Digits, Tokenizers::PreTokenizers::PreTokenizer.from_str(json)[1]
+
+    sequence[1].keep_separators = false
+    assert_equal ["pi", "3", ".", "14"], sequence.pre_tokenize_str("pi 3.14").map(&:first)
+  end
+
+  def test_fixed_length
+    pre_tokenizer = Tokenizers::PreTokenizers::FixedLength.new(2)
+    assert_instance_of Tokenizers::PreTokenizers::FixedLength, pre_tokenizer
+    assert_kind_of Tokenizers::PreTokenizers::PreTokenizer, pre_tokenizer
+
+    assert_equal [["ab", [0, 2]], ["cd", [2, 4]], ["ef", [4, 6]]], pre_tokenizer.pre_tokenize_str("abcdef")
+    assert_equal [["ab", [0, 2]], ["c", [2, 3]]], pre_tokenizer.pre_tokenize_str("abc")
+    assert_equal [["日本", [0, 2]], ["語", [2, 3]]], pre_tokenizer.pre_tokenize_str("日本語")
+    assert_equal [], pre_tokenizer.pre_tokenize_str("")
+
+    assert_raises(ArgumentError) { Tokenizers::PreTokenizers::FixedLength.new(0) }
+  end
+
+  def test_merge_adjacent
+    split = Tokenizers::PreTokenizers::Split.new(Tokenizers::Regex.new("\\p{Han}"), "isolated")
+    assert_equal [["我", [0, 1]], ["爱", [1, 2]], ["你", [2, 3]], [" hello ", [3, 10]], ["世", [10, 11]], ["界", [11, 12]]], split.pre_tokenize_str("我爱你 hello 世界")
+
+    pre_tokenizer = Tokenizers::PreTokenizers::MergeAdjacent.new("\\p{Han}", split)
+    assert_instance_of Tokenizers::PreTokenizers::MergeAdjacent, pre_tokenizer
+    assert_kind_of Tokenizers::PreTokenizers::PreTokenizer, pre_tokenizer
+
+    assert_equal [["我爱你", [0, 3]], [" hello ", [3, 10]], ["世界", [10, 12]]], pre_tokenizer.pre_tokenize_str("我爱你 hello 世界")
+  end
+
   def test_metaspace
     pre_tokenizer = Tokenizers::PreTokenizers::Metaspace.new
     assert_instance_of Tokenizers::PreTokenizers::Metaspace, pre_tokenizer
@@ -70,16 +291,101 @@ class PreTokenizerTest < Minitest::Test
     assert_equal true, pre_tokenizer.add_prefix_space
   end
 
+  def test_metaspace_replacement
+    pre_tokenizer = Tokenizers::PreTokenizers::Metaspace.new(replacement: "\u2581")
+    assert_equal "▁", pre_tokenizer.replacement
+
+    error = assert_raises(ArgumentError) do
+      Tokenizers::PreTokenizers::Metaspace.new(replacement: "ab")
+    end
+    assert_equal "replacement must be exactly one character, got \"ab\"", error.message
+
+    assert_raises(ArgumentError) do
+      Tokenizers::PreTokenizers::Metaspace.new(replacement: "")
+    end
+
+    error = assert_raises(ArgumentError) do
+      pre_tokenizer.replacement = "ab"
+    end
+    assert_equal "replacement must be exactly one character, got \"ab\"", error.message
+    assert_equal "▁", pre_tokenizer.replacement
+
+    pre_tokenizer.replacement = "_"
+    assert_equal "_", pre_tokenizer.replacement
+  end
+
+  def test_metaspace_split
+    pre_tokenizer = Tokenizers::PreTokenizers::Metaspace.new
+    assert_equal true, pre_tokenizer.split
+    assert_equal [["▁Hey", [0, 3]], ["▁friend", [3, 10]]], pre_tokenizer.pre_tokenize_str("Hey friend")
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Metaspace.new(split: false)
+    assert_equal false, pre_tokenizer.split
+    assert_equal [["▁Hey▁friend", [0, 10]]], pre_tokenizer.pre_tokenize_str("Hey friend")
+
+    pre_tokenizer.split = true
+    assert_equal true, pre_tokenizer.split
+    assert_equal [["▁Hey", [0, 3]], ["▁friend", [3, 10]]], pre_tokenizer.pre_tokenize_str("Hey friend")
+
+    json = Tokenizers::PreTokenizers::Metaspace.new(split: false).to_s
+    assert_equal %q({"type":"Metaspace","replacement":"▁","add_prefix_space":true,"split":false}), json
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(json)
+    assert_equal false, pre_tokenizer.split
+    assert_equal [["▁Hey▁friend", [0, 10]]], pre_tokenizer.pre_tokenize_str("Hey friend")
+  end
+
+  def test_metaspace_upstream_json
+    # same as upstream, so stock tokenizers save unchanged
+    json = %q({"type":"Metaspace","replacement":"▁","add_prefix_space":true})
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(json)
+    assert_instance_of Tokenizers::PreTokenizers::Metaspace, pre_tokenizer
+    assert_equal true, pre_tokenizer.split
+    assert_equal json, pre_tokenizer.to_s
+    assert_equal json, Tokenizers::PreTokenizers::Metaspace.new.to_s
+
+    # newer upstream versions also write split: true
+    pre_tokenizer = Tokenizers::PreTokenizers::PreTokenizer.from_str(json.sub("}", %q(,"split":true})))
+    assert_equal true, pre_tokenizer.split
+    assert_equal json, pre_tokenizer.to_s
+  end
+
   def test_punctuation
     pre_tokenizer = Tokenizers::PreTokenizers::Punctuation.new
     assert_instance_of Tokenizers::PreTokenizers::Punctuation, pre_tokenizer
     assert_kind_of Tokenizers::PreTokenizers::PreTokenizer, pre_tokenizer
 
+    assert_equal "isolated", pre_tokenizer.behavior
+
     %w(removed isolated merged_with_previous merged_with_next contiguous).each do |b|
-      Tokenizers::PreTokenizers::Punctuation.new(behavior: b)
+      assert_equal b, Tokenizers::PreTokenizers::Punctuation.new(behavior: b).behavior
     end
 
     assert_raises(ArgumentError) { Tokenizers::PreTokenizers::Punctuation.new(behavior: "invalid") }
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Punctuation.new(scope: :ascii)
+    assert_instance_of Tokenizers::PreTokenizers::Punctuation, pre_tokenizer
+    assert_equal :ascii, pre_tokenizer.scope
+    assert_equal "isolated", pre_tokenizer.behavior
+    assert_equal [["Hello", [0, 5]], [".", [5, 6]], ["你好。", [6, 9]]], pre_tokenizer.pre_tokenize_str("Hello.你好。")
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Punctuation.new(behavior: "removed", scope: :unicode)
+    assert_instance_of Tokenizers::PreTokenizers::Punctuation, pre_tokenizer
+    assert_equal :unicode, pre_tokenizer.scope
+    assert_equal "removed", pre_tokenizer.behavior
+    assert_equal [["Hello.你好", [0, 8]]], pre_tokenizer.pre_tokenize_str("Hello.你好。")
+
+    json = JSON.parse(pre_tokenizer.to_s)
+    assert_equal({"type" => "Punctuation", "behavior" => "Removed", "scope" => "unicode"}, json)
+    loaded = Tokenizers::PreTokenizers::PreTokenizer.from_str(pre_tokenizer.to_s)
+    assert_instance_of Tokenizers::PreTokenizers::Punctuation, loaded
+    assert_equal :unicode, loaded.scope
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Punctuation.new(scope: :all)
+    assert_instance_of Tokenizers::PreTokenizers::Punctuation, pre_tokenizer
+    assert_equal :all, pre_tokenizer.scope
+    assert_equal [["Hello", [0, 5]], [".", [5, 6]], ["你好", [6, 8]], ["。", [8, 9]]], pre_tokenizer.pre_tokenize_str("Hello.你好。")
+
+    assert_raises(ArgumentError) { Tokenizers::PreTokenizers::Punctuation.new(scope: :latin) }
   end
 
   def test_split
@@ -91,15 +397,88 @@ class PreTokenizerTest < Minitest::Test
       Tokenizers::PreTokenizers::Split.new("abc", b)
     end
 
-    Tokenizers::PreTokenizers::Split.new("abc", "isolated", invert: true)
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new("abc", "merged_with_next", invert: true)
+    assert_equal "abc", pre_tokenizer.pattern
+    assert_equal :string, pre_tokenizer.pattern_kind
+    assert_equal "merged_with_next", pre_tokenizer.behavior
+    assert_equal true, pre_tokenizer.invert
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(Tokenizers::Regex.new("\\s+"), "removed")
+    assert_equal "\\s+", pre_tokenizer.pattern
+    assert_equal :regex, pre_tokenizer.pattern_kind
+    assert_equal "removed", pre_tokenizer.behavior
+    assert_equal false, pre_tokenizer.invert
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(/\d+/, "isolated")
+    assert_equal :regex, pre_tokenizer.pattern_kind
 
     assert_raises(ArgumentError) { Tokenizers::PreTokenizers::Split.new("abc", "invalid") }
   end
 
+  def test_split_delimiter_behavior
+    behavior = Tokenizers::PreTokenizers::SplitDelimiterBehavior
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(",", behavior::MERGED_WITH_PREVIOUS)
+    assert_equal "merged_with_previous", pre_tokenizer.behavior
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(",", :contiguous)
+    assert_equal "contiguous", pre_tokenizer.behavior
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Punctuation.new(behavior: behavior::REMOVED)
+    assert_equal "removed", pre_tokenizer.behavior
+
+    error = assert_raises(ArgumentError) do
+      Tokenizers::PreTokenizers::Split.new(",", :isolate)
+    end
+    assert_equal "Wrong value for SplitDelimiterBehavior: \"isolate\", expected one of: removed, isolated, merged_with_previous, merged_with_next, contiguous", error.message
+  end
+
+  def test_split_behaviors
+    expected = {
+      "removed" => [["a", [0, 1]], ["b", [2, 3]], ["c", [5, 6]]],
+      "isolated" => [["a", [0, 1]], [",", [1, 2]], ["b", [2, 3]], [",", [3, 4]], [",", [4, 5]], ["c", [5, 6]]],
+      "merged_with_previous" => [["a,", [0, 2]], ["b,", [2, 4]], [",", [4, 5]], ["c", [5, 6]]],
+      "merged_with_next" => [["a", [0, 1]], [",b", [1, 3]], [",", [3, 4]], [",c", [4, 6]]],
+      "contiguous" => [["a", [0, 1]], [",", [1, 2]], ["b", [2, 3]], [",,", [3, 5]], ["c", [5, 6]]]
+    }
+    expected.each do |behavior, splits|
+      pre_tokenizer = Tokenizers::PreTokenizers::Split.new(",", behavior)
+      assert_equal splits, pre_tokenizer.pre_tokenize_str("a,b,,c"), behavior
+
+      pre_tokenizer = Tokenizers::PreTokenizers::Split.new(Tokenizers::Regex.new(","), behavior)
+      assert_equal splits, pre_tokenizer.pre_tokenize_str("a,b,,c"), behavior
+    end
+
+    # char offsets with multibyte characters
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new("，", "merged_with_previous")
+    assert_equal [["你，", [0, 2]], ["好，", [2, 4]], ["吗", [4, 5]]], pre_tokenizer.pre_tokenize_str("你，好，吗")
+    assert_equal [["你，", [0, 6]], ["好，", [6, 12]], ["吗", [12, 15]]], pre_tokenizer.pre_tokenize_str("你，好，吗", :byte)
+  end
+
+  def test_unicode_scripts
+    pre_tokenizer = Tokenizers::PreTokenizers::UnicodeScripts.new
+    assert_instance_of Tokenizers::PreTokenizers::UnicodeScripts, pre_tokenizer
+    assert_kind_of Tokenizers::PreTokenizers::PreTokenizer, pre_tokenizer
+
+    text = "Hello 世界のひと, Привет"
+    expected = [["Hello ", "Latin"], ["世界のひと", "Han"], [", ", "Common"], ["Привет", "Cyrillic"]]
+    assert_equal expected, pre_tokenizer.scripts(text)
+    assert_equal expected.map(&:first), pre_tokenizer.pre_tokenize_str(text).map(&:first)
+  end
+
   def test_whitespace
     pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
     assert_instance_of Tokenizers::PreTokenizers::Whitespace, pre_tokenizer
     assert_kind_of Tokenizers::PreTokenizers::PreTokenizer, pre_tokenizer
+
+    assert_equal "\\w+|[^\\w\\s]+", pre_tokenizer.pattern
+    assert_equal Tokenizers::PreTokenizers::Whitespace::PATTERN, pre_tokenizer.pattern
+
+    text = "I don't know, 42 times!"
+    expected = pre_tokenizer.pre_tokenize_str(text)
+    assert_equal ["I", "don", "'", "t", "know", ",", "42", "times", "!"], expected.map(&:first)
+
+    split = Tokenizers::PreTokenizers::Split.new(Tokenizers::Regex.new(pre_tokenizer.pattern), "removed", invert: true)
+    assert_equal expected, split.pre_tokenize_str(text)
   end
 
   def test_whitespace_split
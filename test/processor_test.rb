@@ -1,4 +1,6 @@
 require_relative "test_helper"
+require "json"
+require "tmpdir"
 
 class ProcessorlTest < Minitest::Test
   def test_bert_processing
@@ -7,6 +9,16 @@ class ProcessorlTest < Minitest::Test
     assert_kind_of Tokenizers::Processors::PostProcessor, processor
   end
 
+  def test_bert_processing_encode
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.post_processor = Tokenizers::Processors::BertProcessing.new(["[SEP]", 102], ["[CLS]", 101])
+
+    encoded = tokenizer.encode("Hello", "world")
+    assert_equal ["[CLS]", "Hello", "[SEP]", "world", "[SEP]"], encoded.tokens
+    assert_equal [0, 0, 0, 1, 1], encoded.type_ids
+    assert_equal [1, 0, 1, 0, 1], encoded.special_tokens_mask
+  end
+
   def test_byte_level
     processor = Tokenizers::Processors::ByteLevel.new
     assert_instance_of Tokenizers::Processors::ByteLevel, processor
@@ -15,6 +27,16 @@ class ProcessorlTest < Minitest::Test
     Tokenizers::Processors::ByteLevel.new(trim_offsets: false)
   end
 
+  def test_byte_level_trim_offsets_pair
+    tokenizer = Tokenizers.from_pretrained("gpt2")
+    tokenizer.post_processor = Tokenizers::Processors::ByteLevel.new(trim_offsets: true)
+
+    encoded = tokenizer.encode("Hello world", "How are you")
+    assert_equal ["Hello", "Ġworld", "How", "Ġare", "Ġyou"], encoded.tokens
+    assert_equal [[0, 5], [6, 11], [0, 3], [4, 7], [8, 11]], encoded.offsets
+    assert_equal [0, 0, 1, 1, 1], encoded.sequence_ids
+  end
+
   def test_roberta_processing
     processor = Tokenizers::Processors::RobertaProcessing.new(["[SEP]", 1], ["[CLS]", 0])
     assert_instance_of Tokenizers::Processors::RobertaProcessing, processor
@@ -26,6 +48,53 @@ class ProcessorlTest < Minitest::Test
                                                   add_prefix_space: false)
   end
 
+  def test_roberta_processing_trim_offsets_pair
+    tokenizer = Tokenizers.from_pretrained("gpt2")
+    tokenizer.post_processor = Tokenizers::Processors::RobertaProcessing.new(["</s>", 2], ["<s>", 0])
+
+    encoded = tokenizer.encode("Hello world", "How are you")
+    assert_equal ["<s>", "Hello", "Ġworld", "</s>", "</s>", "How", "Ġare", "Ġyou", "</s>"], encoded.tokens
+    assert_equal [0, 2, 2, 2], encoded.ids.values_at(0, 3, 4, 8)
+    assert_equal [[0, 0], [0, 5], [6, 11], [0, 0], [0, 0], [0, 3], [4, 7], [8, 11], [0, 0]], encoded.offsets
+    assert_equal [0] * 9, encoded.type_ids
+    assert_equal [1, 0, 0, 1, 1, 0, 0, 0, 1], encoded.special_tokens_mask
+
+    tokenizer.post_processor = Tokenizers::Processors::RobertaProcessing.new(["</s>", 2], ["<s>", 0], trim_offsets: false)
+    encoded = tokenizer.encode("Hello world", "How are you")
+    assert_equal [[0, 0], [0, 5], [5, 11], [0, 0], [0, 0], [0, 3], [3, 7], [7, 11], [0, 0]], encoded.offsets
+  end
+
+  def test_sequence
+    byte_level = Tokenizers::Processors::ByteLevel.new(trim_offsets: true)
+    template = Tokenizers::Processors::TemplateProcessing.new(
+      single: "<s> $A </s>",
+      special_tokens: [["<s>", 0], ["</s>", 2]]
+    )
+    processor = Tokenizers::Processors::Sequence.new([byte_level, template])
+    assert_instance_of Tokenizers::Processors::Sequence, processor
+    assert_kind_of Tokenizers::Processors::PostProcessor, processor
+
+    tokenizer = Tokenizers.from_pretrained("gpt2")
+    tokenizer.post_processor = processor
+
+    encoded = tokenizer.encode("Hello world")
+    assert_equal ["<s>", "Hello", "Ġworld", "</s>"], encoded.tokens
+    assert_equal [[0, 0], [0, 5], [6, 11], [0, 0]], encoded.offsets
+
+    config = JSON.parse(tokenizer.to_s)
+    assert_equal "Sequence", config["post_processor"]["type"]
+    assert_equal ["ByteLevel", "TemplateProcessing"], config["post_processor"]["processors"].map { |p| p["type"] }
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "tokenizer.json")
+      tokenizer.save(path)
+
+      tokenizer = Tokenizers::Tokenizer.from_file(path)
+      assert_equal config["post_processor"], JSON.parse(tokenizer.to_s)["post_processor"]
+      assert_equal ["<s>", "Hello", "Ġworld", "</s>"], tokenizer.encode("Hello world").tokens
+    end
+  end
+
   def test_template_processing
     processor = Tokenizers::Processors::TemplateProcessing.new
     assert_instance_of Tokenizers::Processors::TemplateProcessing, processor
@@ -40,4 +109,31 @@ class ProcessorlTest < Minitest::Test
       ]
     )
   end
+
+  def test_template_processing_encode
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.post_processor = Tokenizers::Processors::TemplateProcessing.new(
+      single: "[CLS] $A [SEP]",
+      pair: "[CLS] $A [SEP] $B:1 [SEP]:1",
+      special_tokens: [
+        ["[CLS]", tokenizer.token_to_id("[CLS]")],
+        ["[SEP]", tokenizer.token_to_id("[SEP]")]
+      ]
+    )
+
+    encoded = tokenizer.encode("Hello")
+    assert_equal ["[CLS]", "Hello", "[SEP]"], encoded.tokens
+    assert_equal 101, encoded.ids.first
+    assert_equal 102, encoded.ids.last
+    assert_equal [0, 0, 0], encoded.type_ids
+    assert_equal [1, 0, 1], encoded.special_tokens_mask
+
+    encoded = tokenizer.encode("Hello", "world")
+    assert_equal ["[CLS]", "Hello", "[SEP]", "world", "[SEP]"], encoded.tokens
+    assert_equal 101, encoded.ids[0]
+    assert_equal 102, encoded.ids[2]
+    assert_equal 102, encoded.ids[4]
+    assert_equal [0, 0, 0, 1, 1], encoded.type_ids
+    assert_equal [1, 0, 1, 0, 1], encoded.special_tokens_mask
+  end
 end
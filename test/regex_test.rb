@@ -0,0 +1,25 @@
+require_relative "test_helper"
+
+class RegexTest < Minitest::Test
+  def test_regex
+    regex = Tokenizers::Regex.new("\\d+")
+    assert_equal "\\d+", regex.pattern
+
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(regex, "isolated")
+    assert_equal [["abc", [0, 3]], ["123", [3, 6]], ["def", [6, 9]]], pre_tokenizer.pre_tokenize_str("abc123def")
+
+    normalizer = Tokenizers::Normalizers::Replace.new(regex, "#")
+    assert_equal "abc#def#", normalizer.normalize_str("abc123def45")
+
+    # can be reused
+    pre_tokenizer = Tokenizers::PreTokenizers::Split.new(regex, "removed")
+    assert_equal [["abc", [0, 3]], ["def", [6, 9]]], pre_tokenizer.pre_tokenize_str("abc123def")
+  end
+
+  def test_invalid_regex
+    error = assert_raises(RegexpError) do
+      Tokenizers::Regex.new("(abc")
+    end
+    assert_match "end pattern with unmatched parenthesis", error.message
+  end
+end
@@ -1,4 +1,7 @@
 require_relative "test_helper"
+require "json"
+require "stringio"
+require "tmpdir"
 
 class TokenizerTest < Minitest::Test
   def test_from_pretrained_bert
@@ -46,6 +49,23 @@ class TokenizerTest < Minitest::Test
     assert_equal "Model \"bad\" on the Hub doesn't have a tokenizer", error.message
   end
 
+  def test_from_pretrained_bad_auth_token
+    error = assert_raises(Tokenizers::Error) do
+      Tokenizers.from_pretrained("bert-base-cased", auth_token: "bad")
+    end
+    assert_equal "Model \"bert-base-cased\" on the Hub is not accessible with the provided auth token", error.message
+  end
+
+  def test_from_pretrained_revision
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased", revision: "main")
+    assert_equal ["[CLS]", "Hello", "[SEP]"], tokenizer.encode("Hello").tokens
+
+    error = assert_raises(Tokenizers::Error) do
+      Tokenizers.from_pretrained("bert-base-cased", revision: "bad")
+    end
+    assert_equal "Model \"bert-base-cased\" on the Hub doesn't have a tokenizer", error.message
+  end
+
   def test_add_special_tokens
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
 
@@ -60,6 +80,67 @@ class TokenizerTest < Minitest::Test
     assert_equal "I can feel the magic, can you?", tokenizer.decode(encoded.ids)
   end
 
+  def test_add_tokens
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    assert_operator tokenizer.encode("[ENT]", add_special_tokens: false).tokens.size, :>, 1
+
+    assert_equal 1, tokenizer.add_tokens(["[ENT]"])
+    assert_equal 0, tokenizer.add_tokens(["[ENT]"])
+    encoded = tokenizer.encode("Paris [ENT]", add_special_tokens: false)
+    assert_equal ["Paris", "[ENT]"], encoded.tokens
+    assert_equal tokenizer.token_to_id("[ENT]"), encoded.ids.last
+    assert_equal "Paris [ENT]", tokenizer.decode(encoded.ids)
+
+    assert_equal 1, tokenizer.add_special_tokens(["[SPECIAL]"])
+    encoded = tokenizer.encode("Paris [SPECIAL]", add_special_tokens: false)
+    assert_equal ["Paris", "[SPECIAL]"], encoded.tokens
+    assert_equal "Paris", tokenizer.decode(encoded.ids)
+    assert_equal "Paris [SPECIAL]", tokenizer.decode(encoded.ids, skip_special_tokens: false)
+  end
+
+  def test_added_token
+    token = Tokenizers::AddedToken.new("[ENT]")
+    assert_equal "[ENT]", token.content
+    assert_equal false, token.special
+    assert_equal false, token.single_word
+    assert_equal false, token.lstrip
+    assert_equal false, token.rstrip
+    assert_equal true, token.normalized
+
+    token = Tokenizers::AddedToken.new("[ENT]", single_word: true, lstrip: true, rstrip: true, special: true)
+    assert_equal true, token.special
+    assert_equal true, token.single_word
+    assert_equal true, token.lstrip
+    assert_equal true, token.rstrip
+    assert_equal false, token.normalized
+
+    token = Tokenizers::AddedToken.new("[ENT]", normalized: false)
+    assert_equal false, token.normalized
+  end
+
+  def test_add_tokens_lstrip
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    assert_equal 2, tokenizer.add_tokens([Tokenizers::AddedToken.new("[ENT]", lstrip: true), "[REL]"])
+
+    encoded = tokenizer.encode("Paris [ENT] [REL]", add_special_tokens: false)
+    assert_equal ["Paris", "[ENT]", "[REL]"], encoded.tokens
+    assert_equal [[0, 5], [5, 11], [12, 17]], encoded.offsets
+
+    assert_equal 1, tokenizer.add_special_tokens([Tokenizers::AddedToken.new("[SPECIAL]")])
+    encoded = tokenizer.encode("Paris [SPECIAL]", add_special_tokens: false)
+    assert_equal "Paris", tokenizer.decode(encoded.ids)
+  end
+
+  def test_encode_add_special_tokens
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.post_processor = Tokenizers::Processors::BertProcessing.new(["[SEP]", 102], ["[CLS]", 101])
+
+    assert_equal ["[CLS]", "hello", "[SEP]"], tokenizer.encode("hello").tokens
+    assert_equal ["[CLS]", "hello", "[SEP]"], tokenizer.encode("hello", add_special_tokens: true).tokens
+    assert_equal ["hello"], tokenizer.encode("hello", add_special_tokens: false).tokens
+    assert_equal [["hello"]], tokenizer.encode_batch(["hello"], add_special_tokens: false).map(&:tokens)
+  end
+
   def test_char_bpe_tokenizer
     vocab = "test/support/roberta-base-vocab.json"
     merges = "test/support/roberta-base-merges.txt"
@@ -76,11 +157,150 @@ class TokenizerTest < Minitest::Test
     assert_equal "cafeethmagicayo", tokenizer.decode(encoded.ids)
   end
 
+  def test_char_bpe_tokenizer_train
+    tokenizer = Tokenizers::CharBPETokenizer.new
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "corpus.txt")
+      File.write(path, "Low lower lowest\nNew newer newest\n" * 10)
+      tokenizer.train(path, vocab_size: 100, show_progress: false)
+    end
+
+    encoded = tokenizer.encode("lowest newer")
+    assert_equal ["lowest</w>", "newer</w>"], encoded.tokens
+    assert_equal "lowest newer", tokenizer.decode(encoded.ids)
+
+    encoded = tokenizer.encode("lowz")
+    assert_equal ["low", "<unk>"], encoded.tokens
+  end
+
+  def test_bert_word_piece_tokenizer
+    vocab = ["[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "hello", "world", "un", "##aff", "##able", "!"]
+    tokenizer = Tokenizers::BertWordPieceTokenizer.new(vocab.each_with_index.to_h)
+
+    encoded = tokenizer.encode("Hello unaffable world!")
+    assert_equal ["[CLS]", "hello", "un", "##aff", "##able", "world", "!", "[SEP]"], encoded.tokens
+    assert_equal [2, 5, 7, 8, 9, 6, 10, 3], encoded.ids
+    assert_equal "hello unaffable world!", tokenizer.decode(encoded.ids)
+
+    encoded = tokenizer.encode("Hello", "world")
+    assert_equal ["[CLS]", "hello", "[SEP]", "world", "[SEP]"], encoded.tokens
+    assert_equal [0, 0, 0, 1, 1], encoded.type_ids
+
+    tokenizer = Tokenizers::BertWordPieceTokenizer.new(vocab.each_with_index.to_h, lowercase: false)
+    assert_equal ["[CLS]", "[UNK]", "world", "[SEP]"], tokenizer.encode("Hello world").tokens
+
+    error = assert_raises(Tokenizers::Error) do
+      Tokenizers::BertWordPieceTokenizer.new({"[UNK]" => 0, "hello" => 1})
+    end
+    assert_equal "sep_token not found in the vocabulary", error.message
+  end
+
+  def test_byte_level_bpe_tokenizer
+    tokenizer = Tokenizers::ByteLevelBPETokenizer.new
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "corpus.txt")
+      File.write(path, "Hello world! Hello there.\n" * 10)
+      tokenizer.train(path, vocab_size: 300, special_tokens: ["<|endoftext|>"], show_progress: false)
+    end
+
+    encoded = tokenizer.encode("Hello world")
+    assert_equal ["Hello", "Ġworld"], encoded.tokens
+    assert_equal [[0, 5], [5, 11]], encoded.offsets
+
+    [" Hello world", "  Hello 👋 world", "héllo\tthere ✨"].each do |text|
+      assert_equal text, tokenizer.decode(tokenizer.encode(text).ids)
+    end
+  end
+
   def test_id_token_conversion
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
 
     assert_equal 1169, tokenizer.token_to_id("can")
     assert_equal "magic", tokenizer.id_to_token(3974)
+    assert_equal 103, tokenizer.token_to_id("[MASK]")
+    assert_equal "[MASK]", tokenizer.id_to_token(103)
+
+    assert_nil tokenizer.token_to_id("mellifluous")
+    assert_nil tokenizer.id_to_token(100_000)
+  end
+
+  def test_model
+    tokenizer = Tokenizers.from_pretrained("gpt2")
+    model = tokenizer.model
+    assert_instance_of Tokenizers::Models::BPE, model
+    assert_nil model.dropout
+    assert_equal 50257, model.vocab_size
+
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    model = tokenizer.model
+    assert_instance_of Tokenizers::Models::WordPiece, model
+    assert_equal "[UNK]", model.unk_token
+    assert_equal "##", model.continuing_subword_prefix
+
+    tokenizer.model = Tokenizers::Models::WordLevel.new(vocab: {"[UNK]" => 0, "hello" => 1}, unk_token: "[UNK]")
+    assert_instance_of Tokenizers::Models::WordLevel, tokenizer.model
+    assert_equal [1, 0], tokenizer.encode("hello world", add_special_tokens: false).ids
+  end
+
+  def test_decoder
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new)
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::ByteLevel.new(add_prefix_space: false)
+    trainer = Tokenizers::Trainers::BpeTrainer.new(
+      show_progress: false,
+      initial_alphabet: Tokenizers::PreTokenizers::ByteLevel.alphabet
+    )
+    tokenizer.train_from_iterator([" Hello world"], trainer)
+    assert_nil tokenizer.decoder
+
+    ids = tokenizer.encode(" Hello world").ids
+    assert_equal "ĠHello Ġworld", tokenizer.decode(ids)
+
+    tokenizer.decoder = Tokenizers::Decoders::ByteLevel.new
+    assert_instance_of Tokenizers::Decoders::ByteLevel, tokenizer.decoder
+    assert_equal " Hello world", tokenizer.decode(ids)
+  end
+
+  def test_post_processor
+    vocab = {"[UNK]" => 0, "[CLS]" => 1, "[SEP]" => 2, "hello" => 3, "world" => 4}
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: vocab, unk_token: "[UNK]"))
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+    assert_nil tokenizer.post_processor
+    assert_equal ["hello"], tokenizer.encode("hello").tokens
+
+    tokenizer.post_processor = Tokenizers::Processors::TemplateProcessing.new(
+      single: "[CLS] $A [SEP]",
+      pair: "[CLS] $A [SEP] $B:1 [SEP]:1",
+      special_tokens: [["[CLS]", 1], ["[SEP]", 2]]
+    )
+    assert_instance_of Tokenizers::Processors::TemplateProcessing, tokenizer.post_processor
+    assert_equal ["[CLS]", "hello", "[SEP]"], tokenizer.encode("hello").tokens
+    assert_equal ["[CLS]", "hello", "[SEP]", "world", "[SEP]"], tokenizer.encode("hello", "world").tokens
+
+    tokenizer = Tokenizers.from_str(tokenizer.to_s)
+    assert_instance_of Tokenizers::Processors::TemplateProcessing, tokenizer.post_processor
+    assert_equal [1, 3, 2, 4, 2], tokenizer.encode("hello", "world").ids
+  end
+
+  def test_normalizer
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    assert_instance_of Tokenizers::Normalizers::BertNormalizer, tokenizer.normalizer
+    assert_equal "Hello", tokenizer.encode("Hello world").tokens[1]
+
+    tokenizer.normalizer = Tokenizers::Normalizers::Lowercase.new
+    assert_instance_of Tokenizers::Normalizers::Lowercase, tokenizer.normalizer
+    assert_equal "hello", tokenizer.encode("Hello world").tokens[1]
+  end
+
+  def test_pre_tokenizer
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    assert_instance_of Tokenizers::PreTokenizers::BertPreTokenizer, tokenizer.pre_tokenizer
+    assert_equal ["[CLS]", "Hello", ",", "world", "[SEP]"], tokenizer.encode("Hello, world").tokens
+
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::WhitespaceSplit.new
+    assert_instance_of Tokenizers::PreTokenizers::WhitespaceSplit, tokenizer.pre_tokenizer
+    assert_equal ["[CLS]", "Hello", "##,", "world", "[SEP]"], tokenizer.encode("Hello, world").tokens
   end
 
   def test_multibyte_offsets
@@ -93,6 +313,119 @@ class TokenizerTest < Minitest::Test
     assert_equal expected_offsets, encoded.offsets
   end
 
+  def test_char_navigation
+    tokenizer = Tokenizers.from_pretrained("gpt2")
+    text = "I wanted to convert 10000 ¥ to $."
+    encoded = tokenizer.encode(text)
+
+    assert_equal 5, encoded.char_to_token(26)
+    assert_equal [25, 27], encoded.token_to_chars(5)
+    assert_equal " ¥", text[25...27]
+    assert_equal 6, encoded.char_to_token(28)
+    assert_equal 5, encoded.char_to_word(26)
+    assert_nil encoded.char_to_token(100)
+    assert_nil encoded.token_to_chars(100)
+
+    encoded = tokenizer.encode("¥¥", "I wanted")
+    first_size = tokenizer.encode("¥¥").tokens.size
+    assert_equal first_size, encoded.char_to_token(0, 1)
+    assert_equal [0, 1], encoded.token_to_chars(first_size)
+  end
+
+  def test_encoding_fields
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    encoded = tokenizer.encode("I can feel the magic, can you?")
+
+    assert_equal [101, 146, 1169, 1631, 1103, 3974, 117, 1169, 1128, 136, 102], encoded.ids
+    assert_equal [nil, 0, 1, 2, 3, 4, 5, 6, 7, 8, nil], encoded.word_ids
+    assert_equal [nil, 0, 0, 0, 0, 0, 0, 0, 0, 0, nil], encoded.sequence_ids
+    assert_equal [0] * 11, encoded.type_ids
+    assert_equal [[0, 0], [0, 1], [2, 5], [6, 10], [11, 14], [15, 20], [20, 21], [22, 25], [26, 29], [29, 30], [0, 0]], encoded.offsets
+    assert_equal [1] + [0] * 9 + [1], encoded.special_tokens_mask
+    assert_equal [1] * 11, encoded.attention_mask
+    assert_equal [], encoded.overflowing
+
+    # fields are computed on access, so reading them in another order gives the same values
+    encoded = tokenizer.encode("I can feel the magic, can you?")
+    assert_equal ["[CLS]", "I", "can", "feel", "the", "magic", ",", "can", "you", "?", "[SEP]"], encoded.tokens
+    assert_equal [[0, 0], [0, 1], [2, 5], [6, 10], [11, 14], [15, 20], [20, 21], [22, 25], [26, 29], [29, 30], [0, 0]], encoded.offsets
+    assert_equal [101, 146, 1169, 1631, 1103, 3974, 117, 1169, 1128, 136, 102], encoded.ids
+  end
+
+  def test_encoding_truncate_pad
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    encoded = tokenizer.encode("I can feel the magic, can you feel?", add_special_tokens: false)
+    ids = [146, 1169, 1631, 1103, 3974, 117, 1169, 1128, 1631, 136]
+    assert_equal ids, encoded.ids
+
+    encoded.truncate(5, direction: "left")
+    assert_equal ids.last(5), encoded.ids
+    assert_equal [",", "can", "you", "feel", "?"], encoded.tokens
+    assert_equal [ids.first(5)], encoded.overflowing.map(&:ids)
+
+    encoded.pad(8, pad_id: 0, pad_token: "[PAD]")
+    assert_equal ids.last(5) + [0] * 3, encoded.ids
+    assert_equal [1] * 5 + [0] * 3, encoded.attention_mask
+
+    encoded.pad(10, direction: "left", pad_id: 0, pad_type_id: 1, pad_token: "[PAD]")
+    assert_equal ["[PAD]", "[PAD]"], encoded.tokens.first(2)
+    assert_equal [1, 1] + [0] * 8, encoded.type_ids
+
+    assert_raises(ArgumentError) do
+      encoded.truncate(5, stride: 5)
+    end
+    assert_raises(ArgumentError) do
+      encoded.pad(12, direction: "up")
+    end
+  end
+
+  def test_encoding_merge
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    first = tokenizer.encode("Hello world", add_special_tokens: false)
+    second = tokenizer.encode("Good morning", add_special_tokens: false)
+    assert_equal [[0, 5], [6, 11]], first.offsets
+    assert_equal [[0, 4], [5, 12]], second.offsets
+
+    merged = Tokenizers::Encoding.merge([first, second])
+    assert_instance_of Tokenizers::Encoding, merged
+    assert_equal first.ids + second.ids, merged.ids
+    assert_equal first.tokens + second.tokens, merged.tokens
+    assert_equal [[0, 5], [6, 11], [11, 15], [16, 23]], merged.offsets
+
+    merged = Tokenizers::Encoding.merge([first, second], growing_offsets: false)
+    assert_equal first.offsets + second.offsets, merged.offsets
+
+    # inputs are not modified
+    assert_equal [[0, 5], [6, 11]], first.offsets
+  end
+
+  def test_word_alignment
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    encoded = tokenizer.encode("Hugging Face", "unbelievable")
+
+    expected_tokens = ["[CLS]", "Hu", "##gging", "Face", "[SEP]"] + tokenizer.encode("unbelievable", add_special_tokens: false).tokens + ["[SEP]"]
+    assert_equal expected_tokens, encoded.tokens
+    assert_equal [nil, 0, 0, 1, nil] + [0] * (expected_tokens.size - 6) + [nil], encoded.word_ids
+
+    assert_nil encoded.token_to_word(0)
+    assert_equal 0, encoded.token_to_word(2)
+    assert_equal 1, encoded.token_to_word(3)
+    assert_nil encoded.token_to_word(4)
+    assert_equal 0, encoded.token_to_word(5)
+    assert_nil encoded.token_to_word(expected_tokens.size)
+  end
+
+  def test_is_continuation
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    encoded = tokenizer.encode("unbelievable", add_special_tokens: false)
+    assert_operator encoded.tokens.size, :>, 1
+    assert_equal [false] + [true] * (encoded.tokens.size - 1), encoded.is_continuation
+
+    encoded = tokenizer.encode("unbelievable")
+    assert_equal [false, false] + [true] * (encoded.tokens.size - 3) + [false], encoded.is_continuation
+  end
+
   def test_pair_encoding
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
     question = "Am I allowed to pass two text arguments?"
@@ -101,6 +434,33 @@ class TokenizerTest < Minitest::Test
 
     expected_tokens = ["[CLS]", "Am", "I", "allowed", "to", "pass", "two", "text", "arguments", "?", "[SEP]", "Yes", "I", "am", "!", "[SEP]"]
     assert_equal expected_tokens, encoded.tokens
+    assert_equal [0] * 11 + [1] * 5, encoded.type_ids
+    assert_equal [1] + [0] * 9 + [1] + [0] * 4 + [1], encoded.special_tokens_mask
+    assert_equal [1] * 16, encoded.attention_mask
+    assert_equal [[0, 0], [0, 2], [3, 4]], encoded.offsets.first(3)
+    assert_equal [[0, 3], [4, 5], [6, 8], [8, 9], [0, 0]], encoded.offsets.last(5)
+
+    tokenizer.enable_padding(length: 20)
+    encoded = tokenizer.encode(question, answer)
+    assert_equal expected_tokens + ["[PAD]"] * 4, encoded.tokens
+    assert_equal [0] * 11 + [1] * 5 + [0] * 4, encoded.type_ids
+    assert_equal [1] * 16 + [0] * 4, encoded.attention_mask
+    assert_equal [1] + [0] * 9 + [1] + [0] * 4 + [1] * 5, encoded.special_tokens_mask
+  end
+
+  def test_sequence_ids
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    encoded = tokenizer.encode("Where is it?")
+    assert_equal 1, encoded.n_sequences
+    assert_equal [nil, 0, 0, 0, 0, nil], encoded.sequence_ids
+
+    encoded = tokenizer.encode("Where is it?", "Over there")
+    assert_equal 2, encoded.n_sequences
+    assert_equal [nil, 0, 0, 0, 0, nil, 1, 1, nil], encoded.sequence_ids
+
+    context = encoded.sequence_ids.each_index.select { |i| encoded.sequence_ids[i] == 1 }
+    assert_equal ["Over", "there"], encoded.tokens.values_at(*context)
   end
 
   def test_pretokenized_encoding
@@ -117,6 +477,147 @@ class TokenizerTest < Minitest::Test
     assert_equal encoded_wout_pretokenization.tokens, encoded_with_pretokenization.tokens
   end
 
+  def test_pretokenized_word_ids
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    encoded = tokenizer.encode(["New", "York"], is_pretokenized: true)
+    assert_equal ["[CLS]", "New", "York", "[SEP]"], encoded.tokens
+    assert_equal [nil, 0, 1, nil], encoded.word_ids
+
+    encoded = tokenizer.encode(["New York", "City"], is_pretokenized: true, add_special_tokens: false)
+    assert_equal ["New", "York", "City"], encoded.tokens
+    assert_equal [0, 0, 1], encoded.word_ids
+    assert_equal [[0, 3], [4, 8], [0, 4]], encoded.offsets
+
+    encoded = tokenizer.encode_batch([["New", "York"], ["Los", "Angeles"]], is_pretokenized: true)
+    assert_equal [[nil, 0, 1, nil], [nil, 0, 1, nil]], encoded.map(&:word_ids)
+    assert_equal ["[CLS]", "Los", "Angeles", "[SEP]"], encoded[1].tokens
+  end
+
+  def test_encode_input_types
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    expected = tokenizer.encode("hello café").tokens
+
+    to_str = Object.new
+    def to_str.to_str
+      "hello café"
+    end
+
+    assert_equal expected, tokenizer.encode("hello café".freeze).tokens
+    assert_equal expected, tokenizer.encode(to_str).tokens
+    assert_equal expected, tokenizer.encode("hello café".encode("ISO-8859-1")).tokens
+    assert_equal expected, tokenizer.encode("hello café".b).tokens
+    assert_equal ["[CLS]", "hello", "[SEP]"], tokenizer.encode(:hello).tokens
+    assert_equal [expected, ["[CLS]", "hello", "[SEP]", "hello", "[SEP]"]], tokenizer.encode_batch([to_str, [:hello, "hello"]]).map(&:tokens)
+
+    error = assert_raises(Tokenizers::Error) { tokenizer.encode("hello \xFF\xFE".b) }
+    assert_equal "Invalid UTF-8 in input at byte offset 6: \"\\xFF\"", error.message
+
+    assert_raises(Tokenizers::Error) { tokenizer.encode_batch(["hello", "\xFF"]) }
+    assert_raises(Tokenizers::Error) { tokenizer.encode("\xFF".b, profile: true) }
+    assert_raises(TypeError) { tokenizer.encode(1) }
+  end
+
+  def test_encode_batch
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    question = "Am I allowed to pass two text arguments?"
+    answer = "Yes I am!"
+
+    encodings = tokenizer.encode_batch([question, [question, answer]])
+    assert_equal 2, encodings.size
+    assert_equal tokenizer.encode(question).ids, encodings[0].ids
+    assert_equal tokenizer.encode(question, answer).ids, encodings[1].ids
+
+    encodings = tokenizer.encode_batch([question], add_special_tokens: false)
+    assert_equal tokenizer.encode(question, add_special_tokens: false).ids, encodings[0].ids
+  end
+
+  def test_encode_batch_without_parallelism
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    expected = tokenizer.encode_batch(["Hello world", "Good morning"]).map(&:ids)
+
+    previous = ENV["TOKENIZERS_PARALLELISM"]
+    begin
+      Tokenizers.parallelism = false
+      assert_equal false, Tokenizers.parallelism
+      assert_equal expected, tokenizer.encode_batch(["Hello world", "Good morning"]).map(&:ids)
+      assert_equal ["Hello world", "Good morning"], tokenizer.decode_batch(expected)
+    ensure
+      ENV["TOKENIZERS_PARALLELISM"] = previous
+    end
+  end
+
+  def test_encode_batch_with_concurrent_changes
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    text = "I can feel the magic, can you?"
+    expected = tokenizer.encode(text).ids
+
+    thread = Thread.new do
+      10.times.map { tokenizer.encode_batch([text] * 2000) }
+    end
+    100.times do
+      tokenizer.enable_padding(length: 16)
+      tokenizer.add_tokens(["magic"])
+      tokenizer.no_padding
+    end
+    thread.value.each do |encodings|
+      encodings.each do |encoding|
+        assert_equal expected, encoding.ids.first(expected.size)
+      end
+    end
+  end
+
+  def test_decode_batch_with_concurrent_changes
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    text = "I can feel the magic, can you?"
+    ids = tokenizer.encode(text, add_special_tokens: false).ids
+
+    thread = Thread.new do
+      10.times.map { tokenizer.decode_batch([ids] * 2000) }
+    end
+    100.times do
+      tokenizer.decoder = Tokenizers::Decoders::WordPiece.new
+      tokenizer.add_tokens(["magic"])
+    end
+    thread.value.each do |decoded|
+      assert_equal [text] * 2000, decoded
+    end
+  end
+
+  def test_encode_batch_interrupt
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    texts = ["The quick brown fox jumps over the lazy dog. " * 20] * 10_000
+
+    started_at = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+    assert_raises(Timeout::Error) do
+      Timeout.timeout(0.1) { tokenizer.encode_batch(texts) }
+    end
+    assert_operator Process.clock_gettime(Process::CLOCK_MONOTONIC) - started_at, :<, 1
+  end
+
+  def test_fork_after_parallelism
+    skip "fork not supported" unless Process.respond_to?(:fork)
+    skip "parallelism configured" if ENV.key?("TOKENIZERS_PARALLELISM")
+
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.encode_batch(["Hello world", "Good morning"])
+
+    reader, writer = IO.pipe
+    stderr = STDERR.dup
+    begin
+      STDERR.reopen(writer)
+      pid = fork { exit!(Tokenizers.parallelism ? 1 : 0) }
+    ensure
+      STDERR.reopen(stderr)
+    end
+    writer.close
+    Process.wait(pid)
+
+    assert $?.success?
+    assert_match "Disabling parallelism to avoid deadlocks", reader.read
+    assert_equal true, Tokenizers.parallelism
+  end
+
   def test_decode_with_special_tokens
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
 
@@ -125,6 +626,35 @@ class TokenizerTest < Minitest::Test
     assert_equal "[CLS] I can feel the magic, can you? [SEP]", tokenizer.decode(token_ids, skip_special_tokens: false)
   end
 
+  def test_decode_clean_up_tokenization_spaces
+    vocab = {"[UNK]" => 0, "hello" => 1, "." => 2, "world" => 3, "!" => 4}
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: vocab, unk_token: "[UNK]"))
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+
+    ids = tokenizer.encode(" hello .").ids
+    assert_equal "hello .", tokenizer.decode(ids)
+    assert_equal "hello.", tokenizer.decode(ids, clean_up_tokenization_spaces: true)
+
+    sequences = [ids, tokenizer.encode("hello world !").ids]
+    assert_equal ["hello .", "hello world !"], tokenizer.decode_batch(sequences)
+    assert_equal ["hello.", "hello world!"], tokenizer.decode_batch(sequences, clean_up_tokenization_spaces: true)
+  end
+
+  def test_decode_byte_level_unchanged
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new)
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::ByteLevel.new(add_prefix_space: false)
+    tokenizer.decoder = Tokenizers::Decoders::ByteLevel.new
+    trainer = Tokenizers::Trainers::BpeTrainer.new(
+      show_progress: false,
+      initial_alphabet: Tokenizers::PreTokenizers::ByteLevel.alphabet
+    )
+    tokenizer.train_from_iterator(["I do not ."], trainer)
+
+    ids = tokenizer.encode("I do not .").ids
+    assert_equal "I do not .", tokenizer.decode(ids)
+    assert_equal ["I do not ."], tokenizer.decode_batch([ids])
+  end
+
   def test_decode_batch
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
 
@@ -137,6 +667,66 @@ class TokenizerTest < Minitest::Test
     assert_equal [string_1, string_2], tokenizer.decode_batch([token_ids_1, token_ids_2])
 
     assert_equal ["[CLS] #{string_1} [SEP]", "[CLS] #{string_2} [SEP]"], tokenizer.decode_batch([token_ids_1, token_ids_2], skip_special_tokens: false)
+
+    sequences = [token_ids_1, token_ids_2] * 50
+    assert_equal sequences.map { |ids| tokenizer.decode(ids) }, tokenizer.decode_batch(sequences)
+  end
+
+  def test_decode_batch_with_offsets
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    token_ids_1 = [101, 146, 1169, 1631, 1103, 3974, 117, 1169, 1128, 136, 102]
+    token_ids_2 = [101, 7277, 146, 2148, 1106, 2789, 1160, 3087, 9989, 136, 102]
+
+    results = tokenizer.decode_batch_with_offsets([token_ids_1, token_ids_2])
+    assert_equal tokenizer.decode_batch([token_ids_1, token_ids_2]), results.map(&:first)
+
+    text, offsets = results.first
+    assert_equal token_ids_1.size, offsets.size
+    expected = ["", "I", " can", " feel", " the", " magic", ",", " can", " you", "?", ""]
+    assert_equal expected, offsets.map { |s, e| text[s...e] }
+  end
+
+  def test_decode_batch_with_offsets_rewritten_pieces
+    vocab = {"[UNK]" => 0, "[SEP]" => 1, "hello" => 2, "." => 3, "world" => 4}
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: vocab, unk_token: "[UNK]"))
+    tokenizer.add_special_tokens(["[SEP]"])
+    tokenizer.decoder = Tokenizers::Decoders::WordPiece.new
+
+    sequences = [[2, 3], [2, 3, 1, 4]]
+    results = tokenizer.decode_batch_with_offsets(sequences)
+    assert_equal ["hello.", "hello. world"], results.map(&:first)
+
+    # cleanup removes the space the decoder adds before "."
+    text, offsets = results.first
+    assert_equal [[0, 5], [5, 6]], offsets
+    assert_equal ["hello", "."], offsets.map { |s, e| text[s...e] }
+
+    # skipped special tokens have an empty span
+    text, offsets = results.last
+    assert_equal ["hello", ".", "", " world"], offsets.map { |s, e| text[s...e] }
+  end
+
+  def test_decode_batch_with_offsets_byte_level
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new)
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::ByteLevel.new(add_prefix_space: false)
+    tokenizer.decoder = Tokenizers::Decoders::ByteLevel.new
+    # no merges, so each byte is a token
+    trainer = Tokenizers::Trainers::BpeTrainer.new(
+      vocab_size: 256,
+      show_progress: false,
+      initial_alphabet: Tokenizers::PreTokenizers::ByteLevel.alphabet
+    )
+    tokenizer.train_from_iterator(["café 日本"], trainer)
+
+    ids = tokenizer.encode("café 日本").ids
+    assert_equal 12, ids.size
+    text, offsets = tokenizer.decode_batch_with_offsets([ids]).first
+    assert_equal "café 日本", text
+
+    # a char split across tokens belongs to the token that completes it
+    expected = ["c", "a", "f", "", "é", " ", "", "", "日", "", "", "本"]
+    assert_equal expected, offsets.map { |s, e| text[s...e] }
   end
 
   def test_vocab_size
@@ -163,6 +753,201 @@ class TokenizerTest < Minitest::Test
     assert_equal 28998, vocab_with_added_tokens.size
     assert_equal 15011, vocab_with_added_tokens["upstream"]
     assert_equal 28996, vocab_with_added_tokens["mellifluous"]
+    assert_equal (0...tokenizer.vocab_size).to_a, vocab_with_added_tokens.values.sort
+    assert_equal tokenizer.vocab_size(with_added_tokens: false), vocab_without_added_tokens.values.uniq.size
+  end
+
+  def test_dup
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    copy = tokenizer.dup
+    assert_instance_of Tokenizers::Tokenizer, copy
+
+    copy.enable_padding(length: 8)
+    copy.add_tokens(["mellifluous"])
+    copy.model.unk_token = "[UNK2]"
+    copy.normalizer.lowercase = true
+
+    assert_nil tokenizer.padding
+    assert_nil tokenizer.token_to_id("mellifluous")
+    assert_equal "[UNK]", tokenizer.model.unk_token
+    assert_equal false, tokenizer.normalizer.lowercase
+    assert_equal 8, copy.encode("Hello").ids.size
+    assert_equal 3, tokenizer.encode("Hello").ids.size
+
+    copy = tokenizer.clone
+    copy.enable_truncation(2)
+    assert_nil tokenizer.truncation
+  end
+
+  def test_clone
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    def tokenizer.name
+      "bert"
+    end
+    tokenizer.freeze
+
+    copy = tokenizer.clone
+    assert copy.frozen?
+    assert_equal "bert", copy.name
+
+    copy = tokenizer.clone(freeze: false)
+    refute copy.frozen?
+    copy.enable_truncation(3)
+    assert_nil tokenizer.truncation
+    assert_equal ["[CLS]", "Hello", "[SEP]"], copy.encode("Hello world").tokens
+
+    copy = tokenizer.dup
+    refute copy.frozen?
+    refute_respond_to copy, :name
+  end
+
+  def test_normalize
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: {"[UNK]" => 0}, unk_token: "[UNK]"))
+    assert_equal "ﬁne ①", tokenizer.normalize("ﬁne ①")
+
+    tokenizer.normalizer = Tokenizers::Normalizers::NFKC.new
+    assert_equal "fine 1", tokenizer.normalize("ﬁne ①")
+
+    tokenizer.normalizer = Tokenizers::Normalizers::Sequence.new([Tokenizers::Normalizers::NFKC.new, Tokenizers::Normalizers::Lowercase.new])
+    assert_equal "fine 1 ångström", tokenizer.normalize("ﬁne ① ÅNGSTRÖM")
+  end
+
+  def test_encode_profile
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    encoding, stats = tokenizer.encode("Hello world", profile: true)
+    assert_equal tokenizer.encode("Hello world").tokens, encoding.tokens
+    assert_equal ["model", "normalizer", "post_processor", "pre_tokenizer", "total"], stats.keys.sort
+    assert stats.values.all? { |v| v.is_a?(Integer) && v >= 0 }
+
+    stages = stats.values_at("normalizer", "pre_tokenizer", "model", "post_processor").sum
+    assert_operator stages, :<=, stats["total"]
+
+    encoding, _ = tokenizer.encode("Hello", "world", profile: true)
+    assert_equal ["[CLS]", "Hello", "[SEP]", "world", "[SEP]"], encoding.tokens
+
+    # added tokens, truncation, and padding apply like encode
+    tokenizer.add_tokens(["[ENT]"])
+    tokenizer.enable_truncation(4)
+    encoding, _ = tokenizer.encode("Hello [ENT] world", profile: true)
+    assert_equal ["[CLS]", "Hello", "[ENT]", "[SEP]"], encoding.tokens
+    assert_equal tokenizer.encode("Hello [ENT] world").ids, encoding.ids
+
+    assert_raises(ArgumentError) do
+      tokenizer.encode(["Hello", "world"], is_pretokenized: true, profile: true)
+    end
+  end
+
+  def test_encode_profile_matches_encode
+    text = "Héllo wörld, it's   café time!"
+    ["bert-base-cased", "gpt2"].each do |name|
+      tokenizer = Tokenizers.from_pretrained(name)
+      tokenizer.add_special_tokens(["<sep>"])
+      tokenizer.enable_padding(length: 32)
+
+      [[text], [text, "<sep> and more"]].each do |args|
+        expected = tokenizer.encode(*args)
+        encoding, _ = tokenizer.encode(*args, profile: true)
+        assert_equal expected.ids, encoding.ids
+        assert_equal expected.tokens, encoding.tokens
+        assert_equal expected.offsets, encoding.offsets
+        assert_equal expected.type_ids, encoding.type_ids
+        assert_equal expected.attention_mask, encoding.attention_mask
+        assert_equal expected.special_tokens_mask, encoding.special_tokens_mask
+      end
+
+      # the rebuilt added tokens follow later changes
+      tokenizer.add_tokens(["wörld"])
+      encoding, _ = tokenizer.encode(text, profile: true)
+      assert_equal tokenizer.encode(text).ids, encoding.ids
+    end
+  end
+
+  def test_encode_chunks
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    # long enough to be read in several windows
+    text = 5000.times.map { |i| "A mellifluous café sentence number #{i}." }.join(" ")
+    full = tokenizer.encode(text, add_special_tokens: false)
+    token_starts = full.offsets.each_with_index.to_h { |(start, _), i| [start, i] }
+
+    chunks = tokenizer.encode_chunks(text, max_length: 64, chunk_overlap: 16).to_a
+    assert chunks.all? { |c| c.ids.size.between?(1, 64) }
+    # chunk boundaries never split a word
+    assert chunks.none? { |c| c.tokens.first.start_with?("##") }
+
+    # each chunk is a slice of the full encoding, with offsets into the original text
+    ranges = chunks.map do |chunk|
+      start = token_starts.fetch(chunk.offsets.first[0])
+      size = chunk.ids.size
+      assert_equal full.ids[start, size], chunk.ids
+      assert_equal full.offsets[start, size], chunk.offsets
+      assert_equal full.word_ids[start, size], chunk.word_ids
+      start...(start + size)
+    end
+    assert_equal 0, ranges.first.begin
+    assert_equal full.ids.size, ranges.last.end
+    ranges.each_cons(2) do |a, b|
+      assert_operator b.begin, :>, a.begin
+      assert_operator b.begin, :<=, a.end
+      assert_operator a.end - b.begin, :<=, 16
+    end
+
+    start, stop = chunks.last.offsets.last
+    assert_equal ".", text[start...stop]
+
+    assert_equal chunks.map(&:ids), tokenizer.encode_chunks(StringIO.new(text), max_length: 64, chunk_overlap: 16).map(&:ids)
+    assert_equal [["Hello"]], tokenizer.encode_chunks("Hello", max_length: 64).map(&:tokens)
+    assert_empty tokenizer.encode_chunks("", max_length: 64).to_a
+
+    assert_raises(ArgumentError) do
+      tokenizer.encode_chunks(text, max_length: 16, chunk_overlap: 16)
+    end
+
+    # the tokenizer's padding and truncation don't apply to chunks
+    tokenizer.enable_padding(length: 8, direction: "left")
+    tokenizer.enable_truncation(4)
+    assert_equal [["Hello"]], tokenizer.encode_chunks("Hello", max_length: 64).map(&:tokens)
+    assert_equal [[0, 5]], tokenizer.encode_chunks("Hello", max_length: 64).first.offsets
+    assert_equal chunks.map(&:ids), tokenizer.encode_chunks(text, max_length: 64, chunk_overlap: 16).map(&:ids)
+    assert_equal 8, tokenizer.padding["length"]
+    assert_equal 4, tokenizer.truncation["max_length"]
+  end
+
+  def test_added_tokens_decoder
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.add_tokens([Tokenizers::AddedToken.new("[ENT]", lstrip: true)])
+
+    decoder = tokenizer.added_tokens_decoder
+    assert_equal [0, 100, 101, 102, 103, 28996], decoder.keys.sort
+    assert_equal "[MASK]", decoder[103].content
+    assert_equal true, decoder[103].special
+
+    token = decoder[28996]
+    assert_instance_of Tokenizers::AddedToken, token
+    assert_equal "[ENT]", token.content
+    assert_equal false, token.special
+    assert_equal true, token.lstrip
+    assert_equal false, token.rstrip
+  end
+
+  def test_export_import_added_tokens
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.add_tokens(["mellifluous", "malodorous"])
+    tokenizer.add_special_tokens(["[XYZ]"])
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "added_tokens.json")
+      tokenizer.export_added_tokens(path)
+
+      new_tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+      assert_nil new_tokenizer.token_to_id("mellifluous")
+
+      assert_equal 3, new_tokenizer.import_added_tokens(path)
+      assert_equal 28996, new_tokenizer.token_to_id("mellifluous")
+      assert_equal 28997, new_tokenizer.token_to_id("malodorous")
+      assert_equal 28998, new_tokenizer.token_to_id("[XYZ]")
+      assert_equal tokenizer.vocab_size, new_tokenizer.vocab_size
+    end
   end
 
   def test_padding
@@ -179,6 +964,26 @@ class TokenizerTest < Minitest::Test
 
     tokenizer.no_padding
     assert_nil tokenizer.padding
+    assert_equal 3, tokenizer.encode("Hello").ids.size
+  end
+
+  def test_padding_batch
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.enable_padding(pad_to_multiple_of: 8)
+
+    encodings = tokenizer.encode_batch(["I can feel the magic, can you?", "Hello"])
+    assert_equal [16, 16], encodings.map { |e| e.ids.size }
+    assert_equal [0] * 5, encodings[0].ids.last(5)
+    assert_equal ["[CLS]", "Hello", "[SEP]"] + ["[PAD]"] * 13, encodings[1].tokens
+    assert_equal [1] * 3 + [0] * 13, encodings[1].attention_mask
+
+    tokenizer.enable_padding(length: 20, direction: "left", pad_id: 1, pad_token: "[SSS]")
+    encodings = tokenizer.encode_batch(["I can feel the magic, can you?", "Hello"])
+    assert_equal [20, 20], encodings.map { |e| e.ids.size }
+    assert_equal [1] * 17 + [101, 8667, 102], encodings[1].ids
+    assert_equal ["[SSS]"] * 17, encodings[1].tokens.first(17)
+
+    assert_raises(ArgumentError) { tokenizer.enable_padding(direction: "up") }
   end
 
   def test_truncation
@@ -193,8 +998,44 @@ class TokenizerTest < Minitest::Test
     custom_params_with_length = {"max_length"=>2048, "stride"=>20, "strategy"=>"only_first", "direction"=>"left"}
     assert_equal custom_params_with_length, tokenizer.truncation
 
+    tokenizer.enable_truncation(4)
+    assert_equal 4, tokenizer.encode("I can feel the magic, can you?").ids.size
+
     tokenizer.no_truncation
     assert_nil tokenizer.truncation
+    assert_equal 11, tokenizer.encode("I can feel the magic, can you?").ids.size
+  end
+
+  def test_truncation_only_second
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.enable_truncation(10, strategy: "only_second")
+
+    encoded = tokenizer.encode("I can feel", "the magic, can you?")
+    assert_equal ["[CLS]", "I", "can", "feel", "[SEP]", "the", "magic", ",", "can", "[SEP]"], encoded.tokens
+    assert_equal [0] * 5 + [1] * 5, encoded.type_ids
+
+    tokenizer.enable_truncation(10, strategy: "only_second", direction: "left")
+    encoded = tokenizer.encode("I can feel", "the magic, can you?")
+    assert_equal ["[CLS]", "I", "can", "feel", "[SEP]", ",", "can", "you", "?", "[SEP]"], encoded.tokens
+
+    assert_raises(ArgumentError) { tokenizer.enable_truncation(10, strategy: "only_third") }
+    assert_raises(ArgumentError) { tokenizer.enable_truncation(10, direction: "up") }
+  end
+
+  def test_overflowing
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.enable_truncation(6, stride: 2)
+
+    encoded = tokenizer.encode("I can feel the magic, can you?")
+    assert_equal ["[CLS]", "I", "can", "feel", "the", "[SEP]"], encoded.tokens
+
+    overflowing = encoded.overflowing
+    assert_equal 3, overflowing.size
+    assert overflowing.all? { |e| e.is_a?(Tokenizers::Encoding) }
+    assert_equal ["[CLS]", "feel", "the", "magic", ",", "[SEP]"], overflowing[0].tokens
+    assert_equal ["[CLS]", "magic", ",", "can", "you", "[SEP]"], overflowing[1].tokens
+    assert_equal ["[CLS]", "can", "you", "?", "[SEP]"], overflowing[2].tokens
+    assert_equal [[0, 0], [6, 10], [11, 14], [15, 20], [20, 21], [0, 0]], overflowing[0].offsets
   end
 
   def test_serialization
@@ -219,6 +1060,117 @@ class TokenizerTest < Minitest::Test
     assert_equal 28996, new_tokenizer.vocab["mellifluous"]
   end
 
+  def test_save
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "bert", "tokenizer.json")
+      tokenizer.save(path)
+      assert_equal tokenizer.to_s(pretty: true), File.read(path)
+
+      new_tokenizer = Tokenizers.from_file(path)
+      assert_equal tokenizer.to_s, new_tokenizer.to_s
+      assert_equal tokenizer.encode("Hello world").ids, new_tokenizer.encode("Hello world").ids
+
+      tokenizer.save(path, pretty: false)
+      assert_equal tokenizer.to_s, File.read(path)
+
+      file = File.join(dir, "file")
+      File.write(file, "")
+      error = assert_raises(Tokenizers::Error) do
+        tokenizer.save(File.join(file, "tokenizer.json"))
+      end
+      assert_match "Unable to save tokenizer to #{file}/tokenizer.json: ", error.message
+    end
+  end
+
+  def test_tk_version
+    assert_match(/\A\d+\.\d+\.\d+/, Tokenizers::TK_VERSION)
+  end
+
+  def test_from_str
+    tokenizer = Tokenizers::Tokenizer.from_str(<<~JSON)
+      {
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [],
+        "normalizer": {"type": "Lowercase"},
+        "pre_tokenizer": {"type": "Whitespace"},
+        "post_processor": null,
+        "decoder": null,
+        "model": {
+          "type": "WordLevel",
+          "vocab": {"[UNK]": 0, "hello": 1, "world": 2},
+          "unk_token": "[UNK]"
+        }
+      }
+    JSON
+
+    encoded = tokenizer.encode("Hello world !")
+    assert_equal ["hello", "world", "[UNK]"], encoded.tokens
+    assert_equal [1, 2, 0], encoded.ids
+
+    error = assert_raises(Tokenizers::Error) do
+      Tokenizers::Tokenizer.from_str("{\n  \"version\": \"1.0\",\n  oops")
+    end
+    assert_match "line 3 column 3", error.message
+  end
+
+  def test_to_s_from_str_round_trip
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    tokenizer.add_tokens(["mellifluous"])
+    text = "A mellifluous sequence"
+
+    [false, true].each do |pretty|
+      json = tokenizer.to_s(pretty: pretty)
+      assert_equal pretty, json.include?("\n")
+
+      new_tokenizer = Tokenizers.from_str(json)
+      assert_equal tokenizer.encode(text).ids, new_tokenizer.encode(text).ids
+      assert_equal tokenizer.encode(text).tokens, new_tokenizer.encode(text).tokens
+      assert_equal json, new_tokenizer.to_s(pretty: pretty)
+    end
+  end
+
+  def test_from_str_metaspace_split
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(vocab: {"[UNK]" => 0}, unk_token: "[UNK]"))
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Metaspace.new(split: false)
+
+    config = JSON.parse(tokenizer.to_s)
+    assert_equal false, config["pre_tokenizer"]["split"]
+
+    config["pre_tokenizer"].delete("split")
+    tokenizer = Tokenizers.from_str(JSON.generate(config))
+    assert_equal ["[UNK]", "[UNK]"], tokenizer.encode("Hey friend").tokens
+
+    config["pre_tokenizer"]["split"] = false
+    tokenizer = Tokenizers.from_str(JSON.generate(config))
+    assert_equal ["[UNK]"], tokenizer.encode("Hey friend").tokens
+  end
+
+  def test_from_file_invalid_component
+    tokenizer = Tokenizers.from_pretrained("bert-base-cased")
+    config = JSON.parse(tokenizer.to_s)
+    config["pre_tokenizer"] = {
+      "type" => "Sequence",
+      "pretokenizers" => [
+        {"type" => "Whitespace"},
+        {"type" => "Split", "pattern" => {"String" => " "}, "behavior" => "Invalid", "invert" => false}
+      ]
+    }
+
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "tokenizer.json")
+      File.write(path, JSON.generate(config))
+
+      error = assert_raises(Tokenizers::Error) do
+        Tokenizers::Tokenizer.from_file(path)
+      end
+      assert_match "pre_tokenizer (Sequence).pretokenizers[1] (Split): ", error.message
+    end
+  end
+
   def test_num_special_tokens_to_add
     tokenizer = Tokenizers.from_pretrained("bert-base-cased")
     assert_equal 3, tokenizer.num_special_tokens_to_add(true)
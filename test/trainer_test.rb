@@ -1,4 +1,6 @@
 require_relative "test_helper"
+require "pathname"
+require "tmpdir"
 
 class TrainerTest < Minitest::Test
   def test_bpe_trainer
@@ -49,6 +51,131 @@ class TrainerTest < Minitest::Test
     assert_equal "#x#", trainer.end_of_word_suffix
   end
 
+  def test_bpe_trainer_train
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "data.txt")
+      File.write(path, "low lower lowest\n" * 10 + "new newer newest\n" * 10)
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      trainer = Tokenizers::Trainers::BpeTrainer.new(vocab_size: 100, show_progress: false, special_tokens: ["[UNK]"])
+      tokenizer.train([path], trainer)
+
+      vocab = tokenizer.vocab
+      assert_equal 0, vocab["[UNK]"]
+      %w(lo low new lowest newest).each do |token|
+        assert_includes vocab.keys, token
+      end
+      assert_equal ["lowest", "new"], tokenizer.encode("lowest new").tokens
+    end
+  end
+
+  def test_train_multiple_files
+    Dir.mktmpdir do |dir|
+      path1 = File.join(dir, "data1.txt")
+      path2 = File.join(dir, "data2.txt")
+      File.write(path1, "apple banana\n" * 5)
+      File.write(path2, "cherry durian\n" * 5)
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(unk_token: "[UNK]"))
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      trainer = Tokenizers::Trainers::WordLevelTrainer.new(show_progress: false, special_tokens: ["[UNK]"])
+      tokenizer.train([path1, Pathname.new(path2)], trainer)
+
+      assert_equal ["[UNK]", "apple", "banana", "cherry", "durian"], tokenizer.vocab.keys.sort
+      assert_equal ["apple", "durian"], tokenizer.encode("apple durian").tokens
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::WordLevel.new(unk_token: "[UNK]"))
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      tokenizer.train(path2, trainer)
+      assert_equal ["[UNK]", "cherry", "durian"], tokenizer.vocab.keys.sort
+    end
+  end
+
+  def test_train_progress
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "data.txt")
+      File.write(path, "low lower lowest\nnew newer newest\n" * 10)
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      trainer = Tokenizers::Trainers::BpeTrainer.new(vocab_size: 100, special_tokens: ["[UNK]"])
+
+      updates = []
+      tokenizer.train(path, trainer) do |current, total, description|
+        updates << [current, total, description]
+      end
+      assert_equal ["lowest", "new"], tokenizer.encode("lowest new").tokens
+
+      refute_empty updates
+      assert_includes updates, [File.size(path), File.size(path), "Pre-processing files"]
+      assert_equal [1, 1, "Training"], updates.last
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+      error = assert_raises(RuntimeError) do
+        tokenizer.train(path, trainer) { raise "boom" }
+      end
+      assert_equal "boom", error.message
+      assert_empty tokenizer.vocab
+
+      # the block can use the tokenizer while it's training
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      sizes = []
+      tokenizer.train(path, trainer) do |_, _, description|
+        sizes << [description, tokenizer.vocab_size]
+      end
+      assert_equal ["Pre-processing files", 0], sizes.first
+      assert_equal ["Training", 0], sizes[-2]
+      assert_equal ["Training", tokenizer.vocab_size], sizes.last
+    end
+  end
+
+  def test_train_from_iterator
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+    trainer = Tokenizers::Trainers::BpeTrainer.new(vocab_size: 100, show_progress: false, special_tokens: ["[UNK]"])
+
+    corpus = Enumerator.new do |y|
+      10.times do
+        y << "low lower lowest"
+        y << "new newer newest"
+      end
+    end
+    tokenizer.train_from_iterator(corpus, trainer)
+    assert_equal ["lowest", "new"], tokenizer.encode("lowest new").tokens
+
+    corpus = Enumerator.new do |y|
+      y << "abc"
+      raise "boom"
+    end
+    error = assert_raises(RuntimeError) do
+      tokenizer.train_from_iterator(corpus, trainer)
+    end
+    assert_equal "boom", error.message
+    assert_equal ["lowest", "new"], tokenizer.encode("lowest new").tokens
+  end
+
+  def test_train_from_iterator_reentrant
+    tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::BPE.new(unk_token: "[UNK]"))
+    tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+    trainer = Tokenizers::Trainers::BpeTrainer.new(vocab_size: 100, show_progress: false, special_tokens: ["[UNK]"])
+
+    parallelism = ENV["TOKENIZERS_PARALLELISM"]
+    seen = []
+    corpus = Enumerator.new do |y|
+      10.times do
+        # the tokenizer and the parallelism setting are untouched while feeding
+        seen << [tokenizer.vocab_size, ENV["TOKENIZERS_PARALLELISM"]]
+        y << "low lower lowest"
+        y << "new newer newest"
+      end
+    end
+    tokenizer.train_from_iterator(corpus, trainer)
+    assert_equal [[0, parallelism]] * 10, seen
+    assert_equal ["lowest", "new"], tokenizer.encode("lowest new").tokens
+  end
+
   def test_unigram_trainer
     trainer = Tokenizers::Trainers::UnigramTrainer.new
     assert_instance_of Tokenizers::Trainers::UnigramTrainer, trainer
@@ -79,6 +206,39 @@ class TrainerTest < Minitest::Test
     assert_equal ["a"], trainer.initial_alphabet
     trainer.initial_alphabet = ["b"]
     assert_equal ["b"], trainer.initial_alphabet
+
+    assert_in_delta 0.6, trainer.shrinking_factor
+    trainer.shrinking_factor = 0.5
+    assert_in_delta 0.5, trainer.shrinking_factor
+
+    assert_equal "[UNK]", trainer.unk_token
+    trainer.unk_token = "<unk>"
+    assert_equal "<unk>", trainer.unk_token
+
+    assert_equal 32, trainer.max_piece_length
+    trainer.max_piece_length = 16
+    assert_equal 16, trainer.max_piece_length
+
+    assert_equal 3, trainer.n_sub_iterations
+    trainer.n_sub_iterations = 2
+    assert_equal 2, trainer.n_sub_iterations
+  end
+
+  def test_unigram_trainer_train
+    Dir.mktmpdir do |dir|
+      path = File.join(dir, "data.txt")
+      File.write(path, "the quick brown fox jumps over the lazy dog\n" * 20)
+
+      tokenizer = Tokenizers::Tokenizer.new(Tokenizers::Models::Unigram.new)
+      tokenizer.pre_tokenizer = Tokenizers::PreTokenizers::Whitespace.new
+      trainer = Tokenizers::Trainers::UnigramTrainer.new(vocab_size: 50, show_progress: false, special_tokens: ["<unk>"], unk_token: "<unk>")
+      tokenizer.train([path], trainer)
+
+      assert_operator tokenizer.vocab_size, :>, 1
+      assert_operator tokenizer.vocab_size, :<=, 50
+      assert_equal 0, tokenizer.token_to_id("<unk>")
+      assert_equal "thequickbrownfox", tokenizer.encode("the quick brown fox").tokens.join
+    end
   end
 
   def test_word_level_trainer